@@ -6,13 +6,19 @@ use http::{
     HeaderMap, HeaderValue,
     header::{ACCEPT, CONTENT_TYPE, COOKIE, REFERER, USER_AGENT},
 };
+use serde::Deserialize;
 
 use quaero_shared::models::{
     engine::{Engine, TaggedEngine},
-    search::{SearchError, SearchOptions, SearchResult},
+    search::{
+        ImageDetails, NewsDetails, QueryOperators, SearchCategory, SearchError, SearchOptions,
+        SearchResult, SearchResultKind, VideoDetails,
+    },
     user_agent::UserAgent,
 };
 
+use crate::utils::{parse_dimensions, parse_duration, parse_json_attribute, parse_relative_time};
+
 /// An engine which parses search results from Bing.
 pub struct BingEngine;
 
@@ -29,18 +35,41 @@ impl Engine for BingEngine {
         "https://www.bing.com"
     }
 
+    fn categories(&self) -> &'static [SearchCategory] {
+        &[
+            SearchCategory::Web,
+            SearchCategory::News,
+            SearchCategory::Images,
+            SearchCategory::Videos,
+        ]
+    }
+
+    fn category_results_per_page(&self, category: SearchCategory) -> usize {
+        match category {
+            SearchCategory::Images => 35,
+            _ => self.results_per_page(),
+        }
+    }
+
+    fn query_operators(&self) -> QueryOperators {
+        QueryOperators::ALL
+    }
+
     fn url(
         &self,
         query: &str,
-        SearchOptions {
+        options @ SearchOptions {
             page_num,
+            category,
             safe_search,
             date_time_range,
+            ..
         }: &SearchOptions,
     ) -> Result<String, SearchError> {
         // Turns the page number into the index of the first result.
         // Page 0 is `1`, Page 1 is `11`, Page 2 is `21`, etc...
-        let results_per_page = 10;
+        // Images are paged through 35 at a time instead.
+        let results_per_page = self.category_results_per_page(*category);
         let page_start_idx = results_per_page * page_num + 1;
 
         let safe_search = safe_search.as_lowercase_string();
@@ -57,8 +86,28 @@ impl Engine for BingEngine {
             Cow::Borrowed("")
         };
 
+        let language_param = match options.language_code() {
+            Some(language) => Cow::Owned(format!("&setlang={}", urlencoding::encode(language))),
+            None => Cow::Borrowed(""),
+        };
+
+        let region_param = match options.region_code() {
+            Some(region) => Cow::Owned(format!("&cc={}", urlencoding::encode(region))),
+            None => Cow::Borrowed(""),
+        };
+
+        let (vertical_path, count_param) = match category {
+            SearchCategory::Web => ("search", Cow::Borrowed("")),
+            SearchCategory::News => ("news/search", Cow::Borrowed("")),
+            SearchCategory::Images => (
+                "images/search",
+                Cow::Owned(format!("&count={results_per_page}")),
+            ),
+            SearchCategory::Videos => ("videos/search", Cow::Borrowed("")),
+        };
+
         Ok(format!(
-            "https://www.bing.com/search?q={query}&first={page_start_idx}&form=QBLH&safeSearch={safe_search}{date_time_range_param}"
+            "https://www.bing.com/{vertical_path}?q={query}&first={page_start_idx}{count_param}&form=QBLH&safeSearch={safe_search}{date_time_range_param}{language_param}{region_param}"
         ))
     }
 
@@ -83,6 +132,19 @@ impl Engine for BingEngine {
         headers.append(REFERER, HeaderValue::from_static("https://google.com/"));
     }
 
+    fn parse_category(
+        &self,
+        response_text: String,
+        category: SearchCategory,
+    ) -> Result<Vec<(String, SearchResult)>, SearchError> {
+        match category {
+            SearchCategory::Web => self.parse(response_text),
+            SearchCategory::News => parse_news(response_text),
+            SearchCategory::Images => parse_images(response_text),
+            SearchCategory::Videos => parse_videos(response_text),
+        }
+    }
+
     fn parse<'a>(&self, response_text: String) -> Result<Vec<(String, SearchResult)>, SearchError> {
         let dom = html_hybrid_parser::Parser::fast_but_constrained(&response_text);
         let parser = dom.parser();
@@ -144,6 +206,142 @@ impl Engine for BingEngine {
     }
 }
 
+fn parse_news(response_text: String) -> Result<Vec<(String, SearchResult)>, SearchError> {
+    let dom = html_hybrid_parser::Parser::fast_but_constrained(&response_text);
+    let parser = dom.parser();
+
+    let nodes = dom.get_nodes_with_classes(&NEWS_RESULT_CLASSES, parser);
+
+    Ok(nodes
+        .filter_map(|this| {
+            // News cards store most of their data as attributes.
+            let url = this.get_attribute("data-url")?.into_owned();
+
+            let title = this
+                .get_attribute("data-title")
+                .map(|this| html_escape::decode_html_entities(&this).into_owned())
+                .unwrap_or_default();
+
+            let summary = this
+                .get_first_node_with_classes(&NEWS_SUMMARY_CLASSES, parser)
+                .and_then(|this| this.text(parser).map(|this| this.to_string()))
+                .unwrap_or_default();
+
+            let published_at = this
+                .get_first_node_with_classes(&NEWS_SOURCE_CLASSES, parser)
+                .and_then(|this| {
+                    this.get_nodes_with_tag("span", parser).find_map(|this| {
+                        this.get_attribute("aria-label")
+                            .and_then(|this| parse_relative_time(&this))
+                    })
+                });
+
+            let details = NewsDetails {
+                source: this
+                    .get_attribute("data-author")
+                    .map(|this| this.into_owned()),
+                published_at,
+                thumbnail_url: this
+                    .get_first_node_with_tag("img", parser)
+                    .and_then(|this| this.get_attribute("src").map(|this| this.into_owned())),
+            };
+
+            let (url_key, result) = SearchResult::new(title, url, summary);
+            Some((url_key, result.with_kind(SearchResultKind::News(details))))
+        })
+        .collect())
+}
+
+/// The data of an image result, stored as json in the `m` attribute of its link.
+#[derive(Deserialize)]
+struct ImageMetadata {
+    #[serde(rename = "murl")]
+    url: String,
+    #[serde(rename = "t")]
+    title: Option<String>,
+    #[serde(rename = "desc")]
+    summary: Option<String>,
+    #[serde(rename = "turl")]
+    thumbnail_url: Option<String>,
+    #[serde(rename = "purl")]
+    source_page_url: Option<String>,
+}
+
+fn parse_images(response_text: String) -> Result<Vec<(String, SearchResult)>, SearchError> {
+    let dom = html_hybrid_parser::Parser::fast_but_constrained(&response_text);
+    let parser = dom.parser();
+
+    let nodes = dom.get_nodes_with_classes(&IMAGE_RESULT_CLASSES, parser);
+
+    Ok(nodes
+        .filter_map(|this| {
+            // Each image's data is stored as json in the `m` attribute of its link.
+            let link = this.get_first_node_with_classes(&IMAGE_LINK_CLASSES, parser)?;
+            let metadata: ImageMetadata = parse_json_attribute(&link.get_attribute("m")?)?;
+
+            let dimensions = this
+                .get_first_node_with_classes(&IMAGE_INFO_CLASSES, parser)
+                .and_then(|this| this.text(parser).and_then(|this| parse_dimensions(&this)));
+
+            let details = ImageDetails {
+                thumbnail_url: metadata.thumbnail_url,
+                source_page_url: metadata.source_page_url,
+                width: dimensions.map(|(width, _)| width),
+                height: dimensions.map(|(_, height)| height),
+            };
+
+            let (url_key, result) = SearchResult::new(
+                metadata.title.unwrap_or_default(),
+                metadata.url,
+                metadata.summary.unwrap_or_default(),
+            );
+            Some((url_key, result.with_kind(SearchResultKind::Image(details))))
+        })
+        .collect())
+}
+
+fn parse_videos(response_text: String) -> Result<Vec<(String, SearchResult)>, SearchError> {
+    let dom = html_hybrid_parser::Parser::fast_but_constrained(&response_text);
+    let parser = dom.parser();
+
+    let nodes = dom.get_nodes_with_classes(&VIDEO_RESULT_CLASSES, parser);
+
+    Ok(nodes
+        .filter_map(|this| {
+            let url = this.get_attribute("ourl")?.into_owned();
+
+            let title = this
+                .get_first_node_with_classes(&VIDEO_TITLE_CLASSES, parser)
+                .and_then(|this| this.text(parser).map(|this| this.to_string()))
+                .unwrap_or_default();
+
+            let mut meta = this
+                .get_first_node_with_classes(&VIDEO_META_CLASSES, parser)
+                .map(|this| {
+                    this.get_nodes_with_tag("span", parser)
+                        .filter_map(|this| this.text(parser).map(|this| this.to_string()))
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+                .into_iter();
+
+            let details = VideoDetails {
+                duration: this
+                    .get_first_node_with_classes(&VIDEO_DURATION_CLASSES, parser)
+                    .and_then(|this| this.text(parser).and_then(|this| parse_duration(&this))),
+                thumbnail_url: this
+                    .get_first_node_with_tag("img", parser)
+                    .and_then(|this| this.get_attribute("src").map(|this| this.into_owned())),
+                published_at: meta.next().and_then(|this| parse_relative_time(&this)),
+                publisher: meta.next(),
+            };
+
+            let (url_key, result) = SearchResult::new(title, url, String::new());
+            Some((url_key, result.with_kind(SearchResultKind::Video(details))))
+        })
+        .collect())
+}
+
 const SEARCH_RESULT_CLASSES: ClassName = class_names_any! { "b_algo" };
 
 const TITLE_CLASSES: ClassName = class_names_any! { "b_algoheader" };
@@ -153,3 +351,16 @@ const TEXT_SUMMARY_CLASSES: ClassName = class_names_exact! { "b_lineclamp3" };
 
 const CARD_SUMMARY_CLASSES: ClassNames = class_names_exact! { "b_cards2", "slide" };
 const CARD_SUMMARY_CONTENT_CLASSES: ClassName = class_names_exact! { "exsni" };
+
+const NEWS_RESULT_CLASSES: ClassName = class_names_any! { "news-card" };
+const NEWS_SUMMARY_CLASSES: ClassName = class_names_any! { "snippet" };
+const NEWS_SOURCE_CLASSES: ClassName = class_names_any! { "source" };
+
+const IMAGE_RESULT_CLASSES: ClassName = class_names_any! { "imgpt" };
+const IMAGE_LINK_CLASSES: ClassName = class_names_any! { "iusc" };
+const IMAGE_INFO_CLASSES: ClassName = class_names_any! { "img_info" };
+
+const VIDEO_RESULT_CLASSES: ClassName = class_names_any! { "mc_vtvc" };
+const VIDEO_TITLE_CLASSES: ClassName = class_names_any! { "mc_vtvc_title" };
+const VIDEO_META_CLASSES: ClassName = class_names_any! { "mc_vtvc_meta_row" };
+const VIDEO_DURATION_CLASSES: ClassName = class_names_any! { "mc_bc_rc" };
@@ -9,10 +9,15 @@ use http::{
 
 use quaero_shared::models::{
     engine::{Engine, TaggedEngine},
-    search::{DateTimeRange, SearchError, SearchOptions, SearchResult},
+    search::{
+        DateTimeRange, ImageDetails, NewsDetails, QueryOperators, SearchCategory, SearchError,
+        SearchOptions, SearchResult, SearchResultKind, VideoDetails,
+    },
     user_agent::UserAgent,
 };
 
+use crate::utils::{parse_duration, parse_relative_time};
+
 /// An engine which parses search results from Brave.
 pub struct BraveEngine;
 
@@ -29,11 +34,25 @@ impl Engine for BraveEngine {
         "https://search.brave.com"
     }
 
+    fn categories(&self) -> &'static [SearchCategory] {
+        &[
+            SearchCategory::Web,
+            SearchCategory::News,
+            SearchCategory::Images,
+            SearchCategory::Videos,
+        ]
+    }
+
+    fn query_operators(&self) -> QueryOperators {
+        QueryOperators::ALL
+    }
+
     fn url(
         &self,
         query: &str,
         SearchOptions {
             page_num,
+            category,
             date_time_range,
             ..
         }: &SearchOptions,
@@ -60,12 +79,23 @@ impl Engine for BraveEngine {
             Cow::Borrowed("")
         };
 
+        let vertical_path = match category {
+            SearchCategory::Web => "search",
+            SearchCategory::News => "news",
+            SearchCategory::Images => "images",
+            SearchCategory::Videos => "videos",
+        };
+
         Ok(format!(
-            "https://search.brave.com/search?q={query}&offset={page_num}{date_time_range_param}"
+            "https://search.brave.com/{vertical_path}?q={query}&offset={page_num}{date_time_range_param}"
         ))
     }
 
-    fn headers(&self, headers: &mut HeaderMap, SearchOptions { safe_search, .. }: &SearchOptions) {
+    fn headers(
+        &self,
+        headers: &mut HeaderMap,
+        options @ SearchOptions { safe_search, .. }: &SearchOptions,
+    ) {
         let safe_search = safe_search.as_lowercase_string();
 
         headers.insert(USER_AGENT, UserAgent::random_no_js().into());
@@ -78,6 +108,12 @@ impl Engine for BraveEngine {
             COOKIE,
             HeaderValue::from_str(&format!("safe_search={safe_search}")).unwrap(),
         );
+        if let Some(region) = options.region_code()
+            && let Ok(region_cookie) =
+                HeaderValue::from_str(&format!("country={}", region.to_lowercase()))
+        {
+            headers.append(COOKIE, region_cookie);
+        }
         headers.append(REFERER, HeaderValue::from_static("https://google.com/"));
     }
 
@@ -160,6 +196,164 @@ impl Engine for BraveEngine {
             })
             .collect())
     }
+
+    fn parse_category(
+        &self,
+        response_text: String,
+        category: SearchCategory,
+    ) -> Result<Vec<(String, SearchResult)>, SearchError> {
+        match category {
+            SearchCategory::Web => self.parse(response_text),
+            SearchCategory::News => parse_news(response_text),
+            SearchCategory::Images => parse_images(response_text),
+            SearchCategory::Videos => parse_videos(response_text),
+        }
+    }
+}
+
+fn parse_news(response_text: String) -> Result<Vec<(String, SearchResult)>, SearchError> {
+    let decoded_data = html_escape::decode_html_entities(&response_text);
+
+    let dom = html_hybrid_parser::Parser::comprehensive_but_slow(decoded_data.as_ref());
+    let parser = dom.parser();
+
+    let Some(results) = dom.get_first_node_with_id("results", parser) else {
+        return Err(SearchError::NoResultsFound);
+    };
+
+    let nodes = results.get_child_nodes_with_classes(&SEARCH_RESULT_CLASSES, parser);
+
+    Ok(nodes
+        .filter_map(|this| {
+            let link = this.get_first_node_with_tag("a", parser)?;
+
+            let url = link.get_href()?.to_string();
+
+            let title = this
+                .get_first_node_with_classes(&TITLE_CLASSES, parser)
+                .and_then(|this| this.text(parser).map(|this| this.trim().to_string()))
+                .unwrap_or_default();
+
+            let summary = this
+                .get_first_node_with_classes(&MEDIA_SUMMARY_CLASSES, parser)
+                .and_then(|this| this.text(parser).map(|this| this.trim().to_string()))
+                .unwrap_or_default();
+
+            let details = NewsDetails {
+                source: this
+                    .get_first_node_with_classes(&SOURCE_CLASSES, parser)
+                    .and_then(|this| this.text(parser).map(|this| this.trim().to_string())),
+                published_at: this
+                    .get_first_node_with_classes(&AGE_CLASSES, parser)
+                    .and_then(|this| {
+                        this.text(parser)
+                            .and_then(|this| parse_relative_time(&this))
+                    }),
+                thumbnail_url: this
+                    .get_first_node_with_tag("img", parser)
+                    .and_then(|this| this.get_attribute("src").map(|this| this.into_owned())),
+            };
+
+            let (url_key, result) = SearchResult::new(title, url, summary);
+            Some((url_key, result.with_kind(SearchResultKind::News(details))))
+        })
+        .collect())
+}
+
+fn parse_images(response_text: String) -> Result<Vec<(String, SearchResult)>, SearchError> {
+    let decoded_data = html_escape::decode_html_entities(&response_text);
+
+    let dom = html_hybrid_parser::Parser::comprehensive_but_slow(decoded_data.as_ref());
+    let parser = dom.parser();
+
+    let nodes = dom.get_nodes_with_classes(&IMAGE_RESULT_CLASSES, parser);
+
+    Ok(nodes
+        .filter_map(|this| {
+            let image = this.get_first_node_with_tag("img", parser)?;
+
+            // Brave links each image to the page it was found on.
+            let source_page_url = this
+                .get_first_node_with_tag("a", parser)
+                .and_then(|this| this.get_href().map(|this| this.to_string()));
+
+            let url = image
+                .get_attribute("data-src")
+                .or_else(|| image.get_attribute("src"))?
+                .to_string();
+
+            let title = image
+                .get_attribute("alt")
+                .map(|this| this.to_string())
+                .unwrap_or_default();
+
+            let details = ImageDetails {
+                thumbnail_url: image.get_attribute("src").map(|this| this.to_string()),
+                source_page_url,
+                width: image
+                    .get_attribute("width")
+                    .and_then(|this| this.parse().ok()),
+                height: image
+                    .get_attribute("height")
+                    .and_then(|this| this.parse().ok()),
+            };
+
+            let (url_key, result) = SearchResult::new(title, url, String::new());
+            Some((url_key, result.with_kind(SearchResultKind::Image(details))))
+        })
+        .collect())
+}
+
+fn parse_videos(response_text: String) -> Result<Vec<(String, SearchResult)>, SearchError> {
+    let decoded_data = html_escape::decode_html_entities(&response_text);
+
+    let dom = html_hybrid_parser::Parser::comprehensive_but_slow(decoded_data.as_ref());
+    let parser = dom.parser();
+
+    let Some(results) = dom.get_first_node_with_id("results", parser) else {
+        return Err(SearchError::NoResultsFound);
+    };
+
+    let nodes = results.get_child_nodes_with_classes(&SEARCH_RESULT_CLASSES, parser);
+
+    Ok(nodes
+        .filter_map(|this| {
+            let link = this.get_first_node_with_tag("a", parser)?;
+
+            let url = link.get_href()?.to_string();
+
+            let title = this
+                .get_first_node_with_classes(&TITLE_CLASSES, parser)
+                .and_then(|this| this.text(parser).map(|this| this.trim().to_string()))
+                .unwrap_or_default();
+
+            let summary = this
+                .get_first_node_with_classes(&MEDIA_SUMMARY_CLASSES, parser)
+                .and_then(|this| this.text(parser).map(|this| this.trim().to_string()))
+                .unwrap_or_default();
+
+            let details = VideoDetails {
+                duration: this
+                    .get_first_node_with_classes(&DURATION_CLASSES, parser)
+                    .and_then(|this| this.text(parser).and_then(|this| parse_duration(&this))),
+                thumbnail_url: this
+                    .get_first_node_with_tag("img", parser)
+                    .and_then(|this| this.get_attribute("src").map(|this| this.into_owned())),
+                publisher: this
+                    .get_first_node_with_classes(&SOURCE_CLASSES, parser)
+                    .and_then(|this| this.text(parser).map(|this| this.trim().to_string())),
+                published_at: this
+                    .get_first_node_with_classes(&AGE_CLASSES, parser)
+                    .and_then(|this| {
+                        this.text(parser)
+                            .and_then(|this| parse_relative_time(&this))
+                    }),
+            };
+
+            let (url_key, result) = SearchResult::new(title, url, summary);
+            Some((url_key, result.with_kind(SearchResultKind::Video(details))))
+        })
+        .collect())
 }
 
 const SEARCH_RESULT_CLASSES: ClassName = class_names_any! { "snippet" };
@@ -170,3 +364,10 @@ const TITLE_CLASSES: ClassName = class_names_any! { "title" };
 
 const SUMMARY_CLASSES: ClassName = class_names_any! { "content" };
 const SUMMARY_QNA_CLASSES: ClassName = class_names_any! { "inline-qa-answer" };
+
+const MEDIA_SUMMARY_CLASSES: ClassName = class_names_any! { "description" };
+const SOURCE_CLASSES: ClassName = class_names_any! { "netloc" };
+const AGE_CLASSES: ClassName = class_names_any! { "age" };
+const DURATION_CLASSES: ClassName = class_names_any! { "duration" };
+
+const IMAGE_RESULT_CLASSES: ClassName = class_names_any! { "image-result" };
@@ -11,7 +11,7 @@ use http::{
 use quaero_shared::models::{
     engine::{Engine, TaggedEngine},
     sanitized_url::SanitizedUrl,
-    search::{SearchError, SearchOptions, SearchResult},
+    search::{QueryOperators, SearchError, SearchOptions, SearchResult},
     user_agent::UserAgent,
 };
 
@@ -31,10 +31,14 @@ impl Engine for GoogleEngine {
         "https://www.google.com"
     }
 
+    fn query_operators(&self) -> QueryOperators {
+        QueryOperators::ALL
+    }
+
     fn url(
         &self,
         query: &str,
-        SearchOptions {
+        options @ SearchOptions {
             page_num,
             safe_search,
             date_time_range,
@@ -57,8 +61,21 @@ impl Engine for GoogleEngine {
             Cow::Borrowed("")
         };
 
+        let language_param = match options.language_code() {
+            Some(language) => Cow::Owned(format!("&hl={}", urlencoding::encode(language))),
+            None => Cow::Borrowed(""),
+        };
+
+        let region_param = match options.region_code() {
+            Some(region) => Cow::Owned(format!(
+                "&gl={}",
+                urlencoding::encode(&region.to_lowercase())
+            )),
+            None => Cow::Borrowed(""),
+        };
+
         Ok(format!(
-            "https://www.google.com/search?q={query}&ie=utf8&oe=utf8&start={page_start_idx}&filter=0&safe={safe_search}&tbs={date_time_range_param}"
+            "https://www.google.com/search?q={query}&ie=utf8&oe=utf8&start={page_start_idx}&filter=0&safe={safe_search}&tbs={date_time_range_param}{language_param}{region_param}"
         ))
     }
 
@@ -2,7 +2,7 @@
 
 #![warn(missing_docs)]
 
-use quaero_shared::models::engine::TaggedEngine;
+use quaero_shared::models::engine::{EngineRegistry, TaggedEngine};
 
 macro_rules! pub_use_modules {
     ($($name:ident),+) => {
@@ -15,6 +15,8 @@ macro_rules! pub_use_modules {
 
 pub_use_modules![bing, brave, google, mojeek, yahoo, yandex];
 
+mod utils;
+
 /// Constructs a list of the default engines.
 #[inline(always)]
 pub fn default() -> [TaggedEngine; 6] {
@@ -27,3 +29,15 @@ pub fn default() -> [TaggedEngine; 6] {
         YandexEngine::new(),
     ]
 }
+
+/// Constructs a registry of the default engines, registered under their lowercase names
+/// (such as `google`), for building engines from configuration.
+pub fn registry() -> EngineRegistry {
+    EngineRegistry::new()
+        .register("bing", BingEngine::new)
+        .register("brave", BraveEngine::new)
+        .register("google", GoogleEngine::new)
+        .register("mojeek", MojeekEngine::new)
+        .register("yahoo", YahooEngine::new)
+        .register("yandex", YandexEngine::new)
+}
@@ -9,7 +9,7 @@ use http::{
 
 use quaero_shared::models::{
     engine::{Engine, TaggedEngine},
-    search::{DateTimeRange, SearchError, SearchOptions, SearchResult},
+    search::{DateTimeRange, QueryOperators, SearchError, SearchOptions, SearchResult},
     user_agent::UserAgent,
 };
 
@@ -29,10 +29,19 @@ impl Engine for MojeekEngine {
         "https://www.mojeek.com"
     }
 
+    fn query_operators(&self) -> QueryOperators {
+        QueryOperators {
+            phrase: true,
+            exclude: true,
+            site: true,
+            ..QueryOperators::NONE
+        }
+    }
+
     fn url(
         &self,
         query: &str,
-        SearchOptions {
+        options @ SearchOptions {
             page_num,
             safe_search,
             date_time_range,
@@ -77,8 +86,22 @@ impl Engine for MojeekEngine {
             Cow::Borrowed("")
         };
 
+        // Mojeek biases results towards a language and region rather than restricting them.
+        let language_param = match options.language_code() {
+            Some(language) => Cow::Owned(format!("&lb={}", urlencoding::encode(language))),
+            None => Cow::Borrowed(""),
+        };
+
+        let region_param = match options.region_code() {
+            Some(region) => Cow::Owned(format!(
+                "&arc={}",
+                urlencoding::encode(&region.to_lowercase())
+            )),
+            None => Cow::Borrowed(""),
+        };
+
         Ok(format!(
-            "https://www.mojeek.com/search?q={query}{date_time_range_query_param}{page_start_idx_param}{safe_search_param}"
+            "https://www.mojeek.com/search?q={query}{date_time_range_query_param}{page_start_idx_param}{safe_search_param}{language_param}{region_param}"
         ))
     }
 
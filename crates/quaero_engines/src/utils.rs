@@ -0,0 +1,81 @@
+//! Helpers for parsing the extra data found in news, image and video results.
+
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Deserializer, de::DeserializeOwned};
+
+/// Parses how long ago something was published (such as `3 hours ago`, `· 2d` or `1 wk`)
+/// into the time it was published at.
+pub(crate) fn parse_relative_time(text: &str) -> Option<DateTime<Utc>> {
+    let text = text
+        .trim_matches(|char: char| !char.is_alphanumeric())
+        .to_lowercase();
+
+    let digits_len = text.find(|char: char| !char.is_ascii_digit())?;
+    let amount: i64 = text[..digits_len].parse().ok()?;
+    let unit = text[digits_len..].trim_start();
+
+    // Amounts too large to be a real date are ignored rather than overflowing.
+    let duration = match unit {
+        unit if unit.starts_with("mo") => TimeDelta::try_days(amount.checked_mul(30)?)?,
+        unit if unit.starts_with("mi") || unit == "m" => TimeDelta::try_minutes(amount)?,
+        unit if unit.starts_with('s') => TimeDelta::try_seconds(amount)?,
+        unit if unit.starts_with('h') => TimeDelta::try_hours(amount)?,
+        unit if unit.starts_with('d') => TimeDelta::try_days(amount)?,
+        unit if unit.starts_with('w') => TimeDelta::try_weeks(amount)?,
+        unit if unit.starts_with('y') => TimeDelta::try_days(amount.checked_mul(365)?)?,
+        _ => return None,
+    };
+
+    Utc::now().checked_sub_signed(duration)
+}
+
+/// Parses a video's length (such as `12:34` or `1:02:03`).
+pub(crate) fn parse_duration(text: &str) -> Option<Duration> {
+    let mut secs: u64 = 0;
+
+    for part in text.trim().split(':') {
+        secs = secs
+            .checked_mul(60)?
+            .checked_add(part.trim().parse().ok()?)?;
+    }
+
+    Some(Duration::from_secs(secs))
+}
+
+/// Parses an image's dimensions (such as `1920 x 1080 · jpeg`) into its width and height.
+pub(crate) fn parse_dimensions(text: &str) -> Option<(u32, u32)> {
+    let (width, rest) = text.split_once(['x', '×'])?;
+    let height = rest
+        .trim_start()
+        .split(|char: char| !char.is_ascii_digit())
+        .next()?;
+
+    Some((width.trim().parse().ok()?, height.parse().ok()?))
+}
+
+/// Parses a JSON object embedded in an attribute, which may still contain html entities.
+pub(crate) fn parse_json_attribute<T: DeserializeOwned>(json: &str) -> Option<T> {
+    let json = html_escape::decode_html_entities(json);
+    serde_json::from_str(&json).ok()
+}
+
+/// Deserializes a number which may be stored as a string, ignoring values which aren't one.
+pub(crate) fn lenient_u32<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u32>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum LenientU32 {
+        Number(u32),
+        String(String),
+        Other(serde::de::IgnoredAny),
+    }
+
+    Ok(match LenientU32::deserialize(deserializer)? {
+        LenientU32::Number(number) => Some(number),
+        LenientU32::String(string) => string.trim().parse().ok(),
+        LenientU32::Other(_) => None,
+    })
+}
@@ -6,13 +6,19 @@ use http::{
     HeaderMap, HeaderValue,
     header::{ACCEPT, REFERER, USER_AGENT},
 };
+use serde::Deserialize;
 
 use quaero_shared::models::{
     engine::{Engine, TaggedEngine},
-    search::{SafeSearch, SearchError, SearchOptions, SearchResult},
+    search::{
+        ImageDetails, NewsDetails, QueryOperators, SafeSearch, SearchCategory, SearchError,
+        SearchOptions, SearchResult, SearchResultKind, VideoDetails,
+    },
     user_agent::UserAgent,
 };
 
+use crate::utils::{lenient_u32, parse_duration, parse_json_attribute, parse_relative_time};
+
 /// An engine which parses search results from Yahoo.
 pub struct YahooEngine;
 
@@ -29,11 +35,32 @@ impl Engine for YahooEngine {
         "https://search.yahoo.com/search"
     }
 
+    fn categories(&self) -> &'static [SearchCategory] {
+        &[
+            SearchCategory::Web,
+            SearchCategory::News,
+            SearchCategory::Images,
+            SearchCategory::Videos,
+        ]
+    }
+
+    fn category_results_per_page(&self, category: SearchCategory) -> usize {
+        match category {
+            SearchCategory::Images => 60,
+            _ => self.results_per_page(),
+        }
+    }
+
+    fn query_operators(&self) -> QueryOperators {
+        QueryOperators::ALL
+    }
+
     fn url(
         &self,
         query: &str,
-        SearchOptions {
+        options @ SearchOptions {
             page_num,
+            category,
             safe_search,
             date_time_range,
             ..
@@ -41,7 +68,8 @@ impl Engine for YahooEngine {
     ) -> Result<String, SearchError> {
         // Turns the page number into the index of the first result.
         // Page 0 is `1`, Page 1 is `11`, Page 2 is `21`, etc...
-        let results_per_page = 10;
+        // Images are paged through 60 at a time instead.
+        let results_per_page = self.category_results_per_page(*category);
         let page_start_idx = results_per_page * page_num + 1;
 
         let safe_search_param = match safe_search {
@@ -59,8 +87,29 @@ impl Engine for YahooEngine {
             Cow::Borrowed("")
         };
 
+        let language_param = match options.language_code() {
+            Some(language) => Cow::Owned(format!("&vl=lang_{}", urlencoding::encode(language))),
+            None => Cow::Borrowed(""),
+        };
+
+        let region_param = match options.region_code() {
+            Some(region) => Cow::Owned(format!(
+                "&vc={}",
+                urlencoding::encode(&region.to_lowercase())
+            )),
+            None => Cow::Borrowed(""),
+        };
+
+        // Each vertical is served from its own subdomain.
+        let vertical_url = match category {
+            SearchCategory::Web => "https://search.yahoo.com/search",
+            SearchCategory::News => "https://news.search.yahoo.com/search",
+            SearchCategory::Images => "https://images.search.yahoo.com/search/images",
+            SearchCategory::Videos => "https://video.search.yahoo.com/search/video",
+        };
+
         Ok(format!(
-            "https://search.yahoo.com/search?p={query}&b={page_start_idx}{safe_search_param}{date_time_range_param}&nocache=1&nojs=1"
+            "{vertical_url}?p={query}&b={page_start_idx}{safe_search_param}{date_time_range_param}{language_param}{region_param}&nocache=1&nojs=1"
         ))
     }
 
@@ -115,6 +164,150 @@ impl Engine for YahooEngine {
             })
             .collect())
     }
+
+    fn parse_category(
+        &self,
+        response_text: String,
+        category: SearchCategory,
+    ) -> Result<Vec<(String, SearchResult)>, SearchError> {
+        match category {
+            SearchCategory::Web => self.parse(response_text),
+            SearchCategory::News => parse_news(response_text),
+            SearchCategory::Images => parse_images(response_text),
+            SearchCategory::Videos => parse_videos(response_text),
+        }
+    }
+}
+
+fn parse_news(response_text: String) -> Result<Vec<(String, SearchResult)>, SearchError> {
+    let dom = html_hybrid_parser::Parser::fast_but_constrained(&response_text);
+    let parser = dom.parser();
+
+    let nodes = dom.get_nodes_with_classes(&NEWS_RESULT_CLASSES, parser);
+
+    Ok(nodes
+        .filter_map(|this| {
+            let title_node = this.get_first_node_with_classes(&TITLE_CLASSES, parser)?;
+
+            let title = title_node
+                .text(parser)
+                .map(|this| this.to_string())
+                .unwrap_or_default();
+
+            // Results without a link can't be opened or told apart, so they're skipped.
+            let url = title_node
+                .get_first_node_with_tag("a", parser)
+                .and_then(|this| this.get_href().map(|this| clean_url(this.to_string())))
+                .filter(|url| !url.is_empty())?;
+
+            let summary = this
+                .get_first_node_with_classes(&SUMMARY_CLASSES, parser)
+                .and_then(|this| this.text(parser).map(|this| this.to_string()))
+                .unwrap_or_default();
+
+            let details = NewsDetails {
+                source: this
+                    .get_first_node_with_classes(&NEWS_SOURCE_CLASSES, parser)
+                    .and_then(|this| this.text(parser).map(|this| this.trim().to_string())),
+                published_at: this
+                    .get_first_node_with_classes(&NEWS_TIME_CLASSES, parser)
+                    .and_then(|this| {
+                        this.text(parser)
+                            .and_then(|this| parse_relative_time(&this))
+                    }),
+                thumbnail_url: this
+                    .get_first_node_with_tag("img", parser)
+                    .and_then(|this| this.get_attribute("src").map(|this| this.into_owned())),
+            };
+
+            let (url_key, result) = SearchResult::new(title, url, summary);
+            Some((url_key, result.with_kind(SearchResultKind::News(details))))
+        })
+        .collect())
+}
+
+/// The data of an image result, stored as json in its `data` attribute.
+#[derive(Deserialize)]
+struct ImageMetadata {
+    #[serde(rename = "iurl")]
+    url: String,
+    #[serde(rename = "alt")]
+    title: Option<String>,
+    #[serde(rename = "ith")]
+    thumbnail_url: Option<String>,
+    #[serde(rename = "rurl")]
+    source_page_url: Option<String>,
+    #[serde(rename = "w", default, deserialize_with = "lenient_u32")]
+    width: Option<u32>,
+    #[serde(rename = "h", default, deserialize_with = "lenient_u32")]
+    height: Option<u32>,
+}
+
+fn parse_images(response_text: String) -> Result<Vec<(String, SearchResult)>, SearchError> {
+    let dom = html_hybrid_parser::Parser::fast_but_constrained(&response_text);
+    let parser = dom.parser();
+
+    let nodes = dom.get_nodes_with_classes(&IMAGE_RESULT_CLASSES, parser);
+
+    Ok(nodes
+        .filter_map(|this| {
+            // Each image's data is stored as json in its `data` attribute.
+            let metadata: ImageMetadata = parse_json_attribute(&this.get_attribute("data")?)?;
+
+            let details = ImageDetails {
+                thumbnail_url: metadata.thumbnail_url,
+                source_page_url: metadata.source_page_url,
+                width: metadata.width,
+                height: metadata.height,
+            };
+
+            let (url_key, result) = SearchResult::new(
+                metadata.title.unwrap_or_default(),
+                metadata.url,
+                String::new(),
+            );
+            Some((url_key, result.with_kind(SearchResultKind::Image(details))))
+        })
+        .collect())
+}
+
+fn parse_videos(response_text: String) -> Result<Vec<(String, SearchResult)>, SearchError> {
+    let dom = html_hybrid_parser::Parser::fast_but_constrained(&response_text);
+    let parser = dom.parser();
+
+    let nodes = dom.get_nodes_with_classes(&VIDEO_RESULT_CLASSES, parser);
+
+    Ok(nodes
+        .filter_map(|this| {
+            let url = this.get_attribute("data-rurl")?.into_owned();
+
+            let title = this
+                .get_first_node_with_tag("h3", parser)
+                .and_then(|this| this.text(parser).map(|this| this.to_string()))
+                .unwrap_or_default();
+
+            let details = VideoDetails {
+                duration: this
+                    .get_first_node_with_classes(&VIDEO_DURATION_CLASSES, parser)
+                    .and_then(|this| this.text(parser).and_then(|this| parse_duration(&this))),
+                thumbnail_url: this
+                    .get_first_node_with_tag("img", parser)
+                    .and_then(|this| this.get_attribute("src").map(|this| this.into_owned())),
+                publisher: this
+                    .get_first_node_with_classes(&VIDEO_PUBLISHER_CLASSES, parser)
+                    .and_then(|this| this.text(parser).map(|this| this.trim().to_string())),
+                published_at: this
+                    .get_first_node_with_classes(&VIDEO_AGE_CLASSES, parser)
+                    .and_then(|this| {
+                        this.text(parser)
+                            .and_then(|this| parse_relative_time(&this))
+                    }),
+            };
+
+            let (url_key, result) = SearchResult::new(title, url, String::new());
+            Some((url_key, result.with_kind(SearchResultKind::Video(details))))
+        })
+        .collect())
 }
 
 fn clean_url(input_url: String) -> String {
@@ -137,6 +330,17 @@ const TITLE_CLASSES: ClassName = class_names_any! { "s-title" };
 
 const SUMMARY_CLASSES: ClassName = class_names_any! { "s-desc" };
 
+const NEWS_RESULT_CLASSES: ClassName = class_names_any! { "NewsArticle" };
+const NEWS_SOURCE_CLASSES: ClassName = class_names_any! { "s-source" };
+const NEWS_TIME_CLASSES: ClassName = class_names_any! { "s-time" };
+
+const IMAGE_RESULT_CLASSES: ClassName = class_names_any! { "ld" };
+
+const VIDEO_RESULT_CLASSES: ClassName = class_names_any! { "vr" };
+const VIDEO_DURATION_CLASSES: ClassName = class_names_any! { "v-time" };
+const VIDEO_PUBLISHER_CLASSES: ClassName = class_names_any! { "v-meta" };
+const VIDEO_AGE_CLASSES: ClassName = class_names_any! { "v-age" };
+
 const DATE_TIME_PRESETS: [(Duration, &'static str); 3] = [
     (Duration::hours(24), "d"),
     (Duration::weeks(1), "w"),
@@ -10,7 +10,10 @@ use http::{
 
 use quaero_shared::models::{
     engine::{Engine, TaggedEngine},
-    search::{DateTimeRange, SearchError, SearchOptions, SearchResult},
+    search::{
+        DateTimeRange, QueryOperators, QuerySyntax, SearchError, SearchOptions, SearchQuery,
+        SearchResult,
+    },
     user_agent::UserAgent,
 };
 
@@ -30,10 +33,26 @@ impl Engine for YandexEngine {
         "https://yandex.com"
     }
 
+    fn query_operators(&self) -> QueryOperators {
+        QueryOperators::ALL
+    }
+
+    fn render_query(&self, query: &SearchQuery) -> String {
+        query.render(
+            self.query_operators(),
+            &QuerySyntax {
+                file_type: "mime:",
+                in_title: "title:",
+                or: "|",
+                ..Default::default()
+            },
+        )
+    }
+
     fn url(
         &self,
         query: &str,
-        SearchOptions {
+        options @ SearchOptions {
             page_num,
             safe_search,
             date_time_range,
@@ -60,8 +79,14 @@ impl Engine for YandexEngine {
             Cow::Borrowed("")
         };
 
+        // Yandex identifies regions by its own ids rather than by country code.
+        let region_param = match options.region_code().and_then(region_id) {
+            Some(region_id) => Cow::Owned(format!("&lr={region_id}")),
+            None => Cow::Borrowed(""),
+        };
+
         Ok(format!(
-            "https://yandex.com/search/site/?text={query}&p={page_num}&tmpl_version=releases&web=1&frame=1&searchid={SEARCH_ID}&{date_time_range_params}"
+            "https://yandex.com/search/site/?text={query}&p={page_num}&tmpl_version=releases&web=1&frame=1&searchid={SEARCH_ID}&{date_time_range_params}{region_param}"
         ))
     }
 
@@ -124,6 +149,21 @@ impl Engine for YandexEngine {
 // This is the search id from searxng and 4get.
 const SEARCH_ID: &str = "3131712";
 
+/// Returns Yandex's id for a country.
+fn region_id(region: &str) -> Option<u32> {
+    match region {
+        "RU" => Some(225),
+        "UA" => Some(187),
+        "BY" => Some(149),
+        "KZ" => Some(159),
+        "TR" => Some(983),
+        "US" => Some(84),
+        "GB" => Some(102),
+        "DE" => Some(96),
+        _ => None,
+    }
+}
+
 const SEARCH_RESULTS_WRAPPER_CLASSES: ClassName = class_names_any! { "b-serp-list" };
 const SEARCH_RESULT_CLASSES: ClassName = class_names_any! { "b-serp-item" };
 
@@ -0,0 +1,356 @@
+use std::time::Duration;
+
+use chrono::{TimeDelta, Utc};
+use quaero_engines::{BingEngine, BraveEngine, YahooEngine};
+use quaero_shared::models::{
+    engine::TaggedEngine,
+    search::{
+        ImageDetails, NewsDetails, SearchCategory, SearchOptions, SearchResult, SearchResultKind,
+        VideoDetails,
+    },
+};
+
+fn parse(engine: TaggedEngine, category: SearchCategory, fixture: &str) -> Vec<SearchResult> {
+    engine
+        .1
+        .parse_category(fixture.to_string(), category)
+        .unwrap()
+        .into_iter()
+        .map(|(_, result)| result)
+        .collect()
+}
+
+fn news(result: &SearchResult) -> &NewsDetails {
+    match &result.kind {
+        SearchResultKind::News(details) => details,
+        kind => panic!("expected a news result, got {kind:?}"),
+    }
+}
+
+fn image(result: &SearchResult) -> &ImageDetails {
+    match &result.kind {
+        SearchResultKind::Image(details) => details,
+        kind => panic!("expected an image result, got {kind:?}"),
+    }
+}
+
+fn video(result: &SearchResult) -> &VideoDetails {
+    match &result.kind {
+        SearchResultKind::Video(details) => details,
+        kind => panic!("expected a video result, got {kind:?}"),
+    }
+}
+
+/// Asserts that something was published roughly `ago` before now.
+fn assert_published_ago(published_at: Option<chrono::DateTime<Utc>>, ago: TimeDelta) {
+    let published_at = published_at.expect("missing publish time");
+    let error = (Utc::now() - ago - published_at).abs();
+
+    assert!(error < TimeDelta::minutes(1), "published at {published_at}");
+}
+
+#[test]
+fn bing_news() {
+    let results = parse(
+        BingEngine::new(),
+        SearchCategory::News,
+        include_str!("fixtures/bing_news.html"),
+    );
+
+    assert_eq!(results.len(), 2);
+
+    assert_eq!(
+        results[0].title,
+        "Rust 1.80 stabilises LazyLock & exclusive ranges"
+    );
+    assert_eq!(
+        results[0].url,
+        "https://www.theverge.com/2024/7/25/rust-1-80"
+    );
+    assert_eq!(
+        results[0].summary,
+        "The Rust team has released version 1.80 of the language."
+    );
+
+    let details = news(&results[0]);
+    assert_eq!(details.source.as_deref(), Some("The Verge"));
+    assert_eq!(
+        details.thumbnail_url.as_deref(),
+        Some("https://th.bing.com/th?id=OVFT.rust180")
+    );
+    assert_published_ago(details.published_at, TimeDelta::hours(3));
+
+    assert_eq!(results[1].title, "Announcing Rust 1.80.0");
+    assert_eq!(news(&results[1]).published_at, None);
+}
+
+#[test]
+fn bing_images() {
+    let results = parse(
+        BingEngine::new(),
+        SearchCategory::Images,
+        include_str!("fixtures/bing_images.html"),
+    );
+
+    assert_eq!(results.len(), 2);
+
+    assert_eq!(results[0].title, "Ferris the crab");
+    assert_eq!(
+        results[0].url,
+        "https://rustacean.net/assets/rustacean-flat-happy.png"
+    );
+    assert_eq!(results[0].summary, "The unofficial Rust mascot");
+    let details = image(&results[0]);
+    assert_eq!(
+        details.thumbnail_url.as_deref(),
+        Some("https://tse1.mm.bing.net/th?id=OIP.ferris")
+    );
+    assert_eq!(
+        details.source_page_url.as_deref(),
+        Some("https://rustacean.net/")
+    );
+    assert_eq!((details.width, details.height), (Some(1200), Some(800)));
+
+    assert_eq!(results[1].title, "Rust logo");
+    assert_eq!(image(&results[1]).width, None);
+}
+
+#[test]
+fn bing_videos() {
+    let results = parse(
+        BingEngine::new(),
+        SearchCategory::Videos,
+        include_str!("fixtures/bing_videos.html"),
+    );
+
+    assert_eq!(results.len(), 2);
+
+    assert_eq!(results[0].title, "Rust in 100 Seconds");
+    assert_eq!(results[0].url, "https://youtu.be/5C_HPTJg5ek");
+
+    let details = video(&results[0]);
+    assert_eq!(details.duration, Some(Duration::from_secs(145)));
+    assert_eq!(details.publisher.as_deref(), Some("YouTube"));
+    assert_eq!(
+        details.thumbnail_url.as_deref(),
+        Some("https://tse2.mm.bing.net/th?id=OVP.rust100")
+    );
+    assert_published_ago(details.published_at, TimeDelta::days(2 * 365));
+
+    assert_eq!(video(&results[1]).duration, Some(Duration::from_secs(3723)));
+}
+
+#[test]
+fn brave_news() {
+    let results = parse(
+        BraveEngine::new(),
+        SearchCategory::News,
+        include_str!("fixtures/brave_news.html"),
+    );
+
+    assert_eq!(results.len(), 2);
+
+    assert_eq!(results[0].title, "Rust 1.80 lands with lazy statics");
+    assert_eq!(
+        results[0].url,
+        "https://www.theregister.com/2024/07/25/rust_180"
+    );
+    assert_eq!(
+        results[0].summary,
+        "The latest stable release of Rust brings LazyLock to the standard library."
+    );
+
+    let details = news(&results[0]);
+    assert_eq!(details.source.as_deref(), Some("theregister.com"));
+    assert_eq!(
+        details.thumbnail_url.as_deref(),
+        Some("https://imgs.search.brave.com/register.jpg")
+    );
+    assert_published_ago(details.published_at, TimeDelta::days(2));
+
+    assert_eq!(results[1].title, "Rust in the kernel");
+    assert_eq!(news(&results[1]).source, None);
+}
+
+#[test]
+fn brave_images() {
+    let results = parse(
+        BraveEngine::new(),
+        SearchCategory::Images,
+        include_str!("fixtures/brave_images.html"),
+    );
+
+    assert_eq!(results.len(), 2);
+
+    assert_eq!(results[0].title, "Ferris the crab");
+    assert_eq!(
+        results[0].url,
+        "https://rustacean.net/assets/rustacean-flat-happy.png"
+    );
+    let details = image(&results[0]);
+    assert_eq!(
+        details.thumbnail_url.as_deref(),
+        Some("https://imgs.search.brave.com/ferris-thumb.png")
+    );
+    assert_eq!(
+        details.source_page_url.as_deref(),
+        Some("https://rustacean.net/")
+    );
+    assert_eq!((details.width, details.height), (Some(1200), Some(800)));
+
+    // Images without a lazily loaded source use their thumbnail.
+    assert_eq!(
+        results[1].url,
+        "https://imgs.search.brave.com/rust-logo.svg"
+    );
+    assert_eq!(image(&results[1]).source_page_url, None);
+}
+
+#[test]
+fn brave_videos() {
+    let results = parse(
+        BraveEngine::new(),
+        SearchCategory::Videos,
+        include_str!("fixtures/brave_videos.html"),
+    );
+
+    assert_eq!(results.len(), 2);
+
+    assert_eq!(results[0].title, "Rust in 100 Seconds");
+    assert_eq!(results[0].url, "https://youtu.be/5C_HPTJg5ek");
+    assert_eq!(
+        results[0].summary,
+        "Rust is a memory-safe compiled programming language."
+    );
+
+    let details = video(&results[0]);
+    assert_eq!(details.duration, Some(Duration::from_secs(145)));
+    assert_eq!(details.publisher.as_deref(), Some("YouTube"));
+    assert_eq!(
+        details.thumbnail_url.as_deref(),
+        Some("https://imgs.search.brave.com/rust100.jpg")
+    );
+    assert_published_ago(details.published_at, TimeDelta::weeks(3));
+
+    assert_eq!(results[1].title, "Rust at Mozilla");
+    assert_eq!(video(&results[1]).duration, None);
+}
+
+#[test]
+fn yahoo_news() {
+    let results = parse(
+        YahooEngine::new(),
+        SearchCategory::News,
+        include_str!("fixtures/yahoo_news.html"),
+    );
+
+    // Articles without a link are skipped rather than sharing an empty url.
+    assert_eq!(results.len(), 2);
+
+    assert_eq!(results[0].title, "Rust 1.80 is out");
+    assert_eq!(results[0].url, "https://www.zdnet.com/article/rust-1-80");
+    assert_eq!(
+        results[0].summary,
+        "Rust's latest release makes lazy initialisation part of the standard library."
+    );
+
+    let details = news(&results[0]);
+    assert_eq!(details.source.as_deref(), Some("ZDNET"));
+    assert_eq!(
+        details.thumbnail_url.as_deref(),
+        Some("https://s.yimg.com/fz/api/res/zdnet.jpg")
+    );
+    assert_published_ago(details.published_at, TimeDelta::hours(5));
+
+    assert_eq!(
+        results[1].url,
+        "https://www.infoworld.com/article/rust-180.html"
+    );
+}
+
+#[test]
+fn yahoo_images() {
+    let results = parse(
+        YahooEngine::new(),
+        SearchCategory::Images,
+        include_str!("fixtures/yahoo_images.html"),
+    );
+
+    assert_eq!(results.len(), 2);
+
+    assert_eq!(results[0].title, "Ferris the crab");
+    assert_eq!(
+        results[0].url,
+        "https://rustacean.net/assets/rustacean-flat-happy.png"
+    );
+    let details = image(&results[0]);
+    assert_eq!(
+        details.thumbnail_url.as_deref(),
+        Some("https://tse1.mm.bing.net/th?id=OIP.ferris")
+    );
+    assert_eq!(
+        details.source_page_url.as_deref(),
+        Some("https://rustacean.net/")
+    );
+    assert_eq!((details.width, details.height), (Some(1200), Some(800)));
+
+    // Dimensions which aren't numbers are ignored.
+    assert_eq!(results[1].title, "");
+    assert_eq!(image(&results[1]).width, None);
+}
+
+#[test]
+fn yahoo_videos() {
+    let results = parse(
+        YahooEngine::new(),
+        SearchCategory::Videos,
+        include_str!("fixtures/yahoo_videos.html"),
+    );
+
+    assert_eq!(results.len(), 2);
+
+    assert_eq!(results[0].title, "Rust in 100 Seconds");
+    assert_eq!(results[0].url, "https://youtu.be/5C_HPTJg5ek");
+
+    let details = video(&results[0]);
+    assert_eq!(details.duration, Some(Duration::from_secs(145)));
+    assert_eq!(details.publisher.as_deref(), Some("youtube.com"));
+    assert_eq!(
+        details.thumbnail_url.as_deref(),
+        Some("https://tse2.mm.bing.net/th?id=OVP.rust100")
+    );
+    assert_published_ago(details.published_at, TimeDelta::days(30));
+
+    assert_eq!(results[1].url, "https://vimeo.com/123456");
+}
+
+#[test]
+fn image_pages() {
+    let options = SearchOptions::default()
+        .category(SearchCategory::Images)
+        .page_num(1);
+
+    let bing = BingEngine::new();
+    assert_eq!(bing.1.category_results_per_page(SearchCategory::Images), 35);
+    assert_eq!(bing.1.category_results_per_page(SearchCategory::Web), 10);
+    assert!(
+        bing.1
+            .url("rust", &options)
+            .unwrap()
+            .contains("first=36&count=35")
+    );
+
+    let yahoo = YahooEngine::new();
+    assert_eq!(
+        yahoo.1.category_results_per_page(SearchCategory::Images),
+        60
+    );
+    assert!(yahoo.1.url("rust", &options).unwrap().contains("&b=61&"));
+    assert!(
+        yahoo
+            .1
+            .url("rust", &options.page_num(1).category(SearchCategory::Web))
+            .unwrap()
+            .contains("&b=11&")
+    );
+}
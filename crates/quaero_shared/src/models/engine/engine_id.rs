@@ -1,9 +1,21 @@
-use std::fmt::Debug;
+use std::{
+    fmt::{Debug, Display},
+    str::FromStr,
+};
 
 use nanoid::nanoid;
+use thiserror::Error;
 
 /// A unique identifier for a particular engine.
+///
+/// Its string form (and serialized form) is the engine's name and unique random
+/// string separated by a colon, such as `Google:V1StGXR8_Z`.
 #[derive(Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
 pub struct EngineId {
     name: String,
     id: String,
@@ -17,10 +29,60 @@ impl EngineId {
             id: nanoid!(10),
         }
     }
+
+    /// Returns the name of the engine.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the unique random string which tells engines with the same name apart.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
 }
 
 impl Debug for EngineId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "EngineId({}:{})", self.name, self.id)
+        write!(f, "EngineId({self})")
+    }
+}
+
+impl Display for EngineId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.name, self.id)
+    }
+}
+
+/// The error returned when a string isn't a valid [EngineId].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Engine ids need to be formatted as `name:id`.")]
+pub struct ParseEngineIdError;
+
+impl FromStr for EngineId {
+    type Err = ParseEngineIdError;
+
+    fn from_str(engine_id: &str) -> Result<Self, Self::Err> {
+        // Names can contain colons, unlike the random strings.
+        match engine_id.rsplit_once(':') {
+            Some((name, id)) if !name.is_empty() && !id.is_empty() => Ok(Self {
+                name: name.to_string(),
+                id: id.to_string(),
+            }),
+            _ => Err(ParseEngineIdError),
+        }
+    }
+}
+
+impl From<EngineId> for String {
+    fn from(engine_id: EngineId) -> Self {
+        engine_id.to_string()
+    }
+}
+
+impl TryFrom<String> for EngineId {
+    type Error = ParseEngineIdError;
+
+    fn try_from(engine_id: String) -> Result<Self, Self::Error> {
+        engine_id.parse()
     }
 }
@@ -0,0 +1,55 @@
+use std::{collections::HashMap, fmt::Debug};
+
+use crate::models::engine::TaggedEngine;
+
+type EngineFactory = Box<dyn Fn() -> TaggedEngine + Send + Sync>;
+
+/// A lookup of engine constructors by name, used to build engines from configuration.
+///
+/// Names are case-insensitive, so `google` and `Google` refer to the same engine.
+#[derive(Default)]
+pub struct EngineRegistry {
+    factories: HashMap<String, EngineFactory>,
+}
+
+impl EngineRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a constructor for an engine under the specified name,
+    /// replacing any constructor already registered under it.
+    pub fn register(
+        mut self,
+        name: impl AsRef<str>,
+        factory: impl Fn() -> TaggedEngine + Send + Sync + 'static,
+    ) -> Self {
+        self.factories
+            .insert(name.as_ref().to_lowercase(), Box::new(factory));
+        self
+    }
+
+    /// Constructs a new instance of the engine registered under the specified name.
+    pub fn create(&self, name: &str) -> Option<TaggedEngine> {
+        self.factories
+            .get(&name.to_lowercase())
+            .map(|factory| factory())
+    }
+
+    /// Returns whether an engine is registered under the specified name.
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(&name.to_lowercase())
+    }
+
+    /// Returns the name of each registered engine.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.factories.keys().map(String::as_str)
+    }
+}
+
+impl Debug for EngineRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.factories.keys()).finish()
+    }
+}
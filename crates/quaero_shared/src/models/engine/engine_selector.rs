@@ -0,0 +1,52 @@
+use crate::models::engine::EngineId;
+
+/// Selects engines either by their identifier or by their name.
+///
+/// Serialized as `{ "id": "Google:V1StGXR8_Z" }` or `{ "name": "google" }`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum EngineSelector {
+    /// Selects the engine with this identifier.
+    Id(EngineId),
+
+    /// Selects every engine with this name, ignoring case.
+    Name(String),
+}
+
+impl EngineSelector {
+    /// Returns whether the engine with the specified identifier is selected.
+    pub fn matches(&self, engine_id: &EngineId) -> bool {
+        match self {
+            EngineSelector::Id(id) => id == engine_id,
+            EngineSelector::Name(name) => engine_id.name().eq_ignore_ascii_case(name),
+        }
+    }
+}
+
+impl From<EngineId> for EngineSelector {
+    fn from(engine_id: EngineId) -> Self {
+        Self::Id(engine_id)
+    }
+}
+
+impl From<&EngineId> for EngineSelector {
+    fn from(engine_id: &EngineId) -> Self {
+        Self::Id(engine_id.clone())
+    }
+}
+
+impl From<String> for EngineSelector {
+    fn from(name: String) -> Self {
+        Self::Name(name)
+    }
+}
+
+impl From<&str> for EngineSelector {
+    fn from(name: &str) -> Self {
+        Self::Name(name.to_string())
+    }
+}
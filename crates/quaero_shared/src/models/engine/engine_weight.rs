@@ -0,0 +1,43 @@
+use std::{
+    fmt::Debug,
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
+};
+
+/// How much an engine's results are trusted relative to other engines.
+///
+/// Clones share the same underlying value, so the weight can be adjusted at
+/// runtime and the change will be picked up by the next search.
+#[derive(Clone)]
+pub struct EngineWeight(Arc<AtomicU32>);
+
+impl EngineWeight {
+    /// Creates a new engine weight. Negative weights are clamped to `0.0`.
+    pub fn new(weight: f32) -> Self {
+        Self(Arc::new(AtomicU32::new(weight.max(0.).to_bits())))
+    }
+
+    /// Returns the current weight.
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    /// Sets the weight. Negative weights are clamped to `0.0`.
+    pub fn set(&self, weight: f32) {
+        self.0.store(weight.max(0.).to_bits(), Ordering::Relaxed);
+    }
+}
+
+impl Default for EngineWeight {
+    fn default() -> Self {
+        Self::new(1.)
+    }
+}
+
+impl Debug for EngineWeight {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "EngineWeight({})", self.get())
+    }
+}
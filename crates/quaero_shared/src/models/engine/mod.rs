@@ -1,12 +1,21 @@
 mod engine_id;
+mod engine_registry;
+mod engine_selector;
+mod engine_weight;
 use std::{any::type_name_of_val, sync::Arc};
 
 use anyhttp::Response;
 pub use engine_id::*;
+pub use engine_registry::*;
+pub use engine_selector::*;
+pub use engine_weight::*;
 use http::{HeaderMap, HeaderValue};
 use smallvec::SmallVec;
 
-use crate::models::search::{SearchError, SearchOptions, SearchResult};
+use crate::models::search::{
+    QueryOperators, QuerySyntax, SearchCategory, SearchError, SearchOptions, SearchQuery,
+    SearchResult,
+};
 
 /// Defines the interface for a search engine.
 #[async_trait::async_trait]
@@ -38,6 +47,35 @@ pub trait Engine: Send + Sync {
     /// Gets the homepage url for this engine.
     fn homepage(&self) -> &'static str;
 
+    /// Returns how many search results the engine returns per page.
+    fn results_per_page(&self) -> usize {
+        10
+    }
+
+    /// Returns how many search results the engine returns per page of a specific category.
+    /// Defaults to [Engine::results_per_page], so only engines which page through
+    /// other verticals differently need to implement it.
+    fn category_results_per_page(&self, _category: SearchCategory) -> usize {
+        self.results_per_page()
+    }
+
+    /// Returns the verticals the engine can search in.
+    /// Engines are skipped for searches in any other category.
+    fn categories(&self) -> &'static [SearchCategory] {
+        &[SearchCategory::Web]
+    }
+
+    /// Returns the search operators the engine understands. Any other operators
+    /// are left out of the query and are enforced on the engine's results instead.
+    fn query_operators(&self) -> QueryOperators {
+        QueryOperators::NONE
+    }
+
+    /// Renders a parsed query into the engine's own syntax, before it is encoded and passed to [Engine::url].
+    fn render_query(&self, query: &SearchQuery) -> String {
+        query.render(self.query_operators(), &QuerySyntax::default())
+    }
+
     /// Returns the url for a particular query.
     fn url(&self, query: &str, options: &SearchOptions) -> Result<String, SearchError>;
 
@@ -51,16 +89,37 @@ pub trait Engine: Send + Sync {
 
     /// Parses search results from the response text.
     fn parse<'a>(&self, response_text: String) -> Result<Vec<(String, SearchResult)>, SearchError>;
+
+    /// Parses search results of a specific category from the response text.
+    /// Defaults to [Engine::parse], so only engines which support other verticals need to implement it.
+    fn parse_category(
+        &self,
+        response_text: String,
+        _category: SearchCategory,
+    ) -> Result<Vec<(String, SearchResult)>, SearchError> {
+        self.parse(response_text)
+    }
 }
 
-/// An engine tagged with a unique identifier.
+/// An engine tagged with a unique identifier and how much its results are trusted.
 /// Used to identify multiple of the same engine from each other.
-pub struct TaggedEngine(pub EngineId, pub Arc<dyn Engine>);
+#[derive(Clone)]
+pub struct TaggedEngine(pub EngineId, pub Arc<dyn Engine>, pub EngineWeight);
 
 impl TaggedEngine {
     /// Creates a new tagged engine from an engine.
     pub fn new(engine: impl Engine + 'static) -> Self {
-        Self(EngineId::from_name(engine.name()), Arc::new(engine))
+        Self(
+            EngineId::from_name(engine.name()),
+            Arc::new(engine),
+            EngineWeight::default(),
+        )
+    }
+
+    /// Sets how much this engine's results are trusted relative to other engines (defaults to `1.0`).
+    pub fn weight(self, weight: f32) -> Self {
+        self.2.set(weight);
+        self
     }
 }
 
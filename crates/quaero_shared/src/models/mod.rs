@@ -12,5 +12,11 @@ pub mod search;
 /// Model for building User Agents.
 pub mod user_agent;
 
+/// Model for scoring search results before they are fused.
+pub mod ranker;
+
 /// Model for refining the score of each search result.
 pub mod score_refiner;
+
+/// Model for caching search responses.
+pub mod search_cache;
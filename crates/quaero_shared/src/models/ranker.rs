@@ -0,0 +1,32 @@
+use crate::models::search::{SearchResultWithMetadata, TokenContribution};
+
+/// A trait for defining how relevant each search result's contents are to the query,
+/// before it is fused with the rank each engine gave it.
+pub trait Ranker: Send + Sync {
+    /// Scores every merged search result, returning one score per result in the same order.
+    ///
+    /// The query tokens are the lowercase words of the query's text, excluding stop words
+    /// and including any synonyms. The results are scored together so that corpus-wide
+    /// statistics (such as how many results contain a token) can be used.
+    fn scores(
+        &self,
+        query: &str,
+        query_tokens: &[String],
+        results: &[SearchResultWithMetadata],
+    ) -> Box<[f32]>;
+
+    /// Breaks each search result's score down into how much each query token contributed,
+    /// returning one list per result in the same order. Used to explain scores, so it
+    /// returns `None` by default for rankers which can't break their scores down.
+    ///
+    /// [TokenContribution::synonym_of] is filled in by the caller.
+    fn token_scores(
+        &self,
+        query: &str,
+        query_tokens: &[String],
+        results: &[SearchResultWithMetadata],
+    ) -> Option<Box<[Vec<TokenContribution>]>> {
+        let _ = (query, query_tokens, results);
+        None
+    }
+}
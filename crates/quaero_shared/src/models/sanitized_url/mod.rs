@@ -271,6 +271,15 @@ pub static PUBLIC_SUFFIX_LIST: LazyLock<RwLock<List>> = LazyLock::new(|| {
     )
 });
 
+/// Returns whether the host is (or is a subdomain of) a domain which can be registered,
+/// rather than a bare public suffix such as `de` or `co.uk`.
+/// Doesn't wait for the list, returning `false` while it's being refreshed.
+pub fn is_registrable(host: &str) -> bool {
+    PUBLIC_SUFFIX_LIST
+        .try_read()
+        .is_ok_and(|list| list.domain(host.as_bytes()).is_some())
+}
+
 /// Fetches an up to date copy of the public suffix list.
 pub async fn refresh_public_suffix_list(client: impl HttpClient + 'static) -> anyhow::Result<()> {
     let request =
@@ -0,0 +1,22 @@
+/// Whether a search response was fetched from the engines or served from a cache.
+///
+/// Serialized in lowercase, such as `"hit"`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum CacheStatus {
+    /// The response was freshly fetched from the engines.
+    /// This is also the case when no cache is configured.
+    #[default]
+    Miss,
+
+    /// The response was served from a cache and is still within its time to live.
+    Hit,
+
+    /// The response was served from a cache after its time to live had passed,
+    /// while a fresh response is fetched in the background.
+    Stale,
+}
@@ -1,6 +1,10 @@
 use chrono::{DateTime, Utc};
 
 /// A range between two DateTime's
+///
+/// Serialized as `{ "start": "2025-01-01T00:00:00Z", "end": "2025-02-01T00:00:00Z" }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DateTimeRange {
     /// The start of the range.
     pub start: DateTime<Utc>,
@@ -0,0 +1,119 @@
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use thiserror::Error;
+
+/// The language (and optionally the region) search results should be in,
+/// such as `de`, `de-DE` or `ja-JP`.
+///
+/// Serialized as its language tag, such as `"de-DE"`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+pub struct Locale {
+    language: String,
+    region: Option<String>,
+}
+
+impl Locale {
+    /// Creates a new locale from a language tag (such as `de`, `de-DE` or `de_DE`).
+    /// Any script or variant subtags (such as `Hant` in `zh-Hant-TW`) are ignored.
+    ///
+    /// Returns `None` if the language isn't two or three letters, as it ends up in engine urls.
+    pub fn new(tag: &str) -> Option<Self> {
+        let mut subtags = tag.trim().split(['-', '_']);
+
+        let language = subtags.next().unwrap_or_default();
+        if !is_language_code(language) {
+            return None;
+        }
+
+        let region = subtags
+            .find(|subtag| is_region_code(subtag))
+            .map(|region| region.to_uppercase());
+
+        Some(Self {
+            language: language.to_lowercase(),
+            region,
+        })
+    }
+
+    /// Returns the lowercase ISO 639-1 language code (such as `de`).
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Returns the uppercase ISO 3166-1 region code (such as `DE`), if one was specified.
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
+    /// Returns the value for an `Accept-Language` header which prefers this locale.
+    pub fn to_accept_language(&self) -> String {
+        match &self.region {
+            Some(region) => format!("{}-{region},{};q=0.9", self.language, self.language),
+            None => self.language.clone(),
+        }
+    }
+}
+
+impl Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.region {
+            Some(region) => write!(f, "{}-{region}", self.language),
+            None => f.write_str(&self.language),
+        }
+    }
+}
+
+/// The error returned when a language tag isn't a valid [Locale].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Locales need to start with a two or three letter language code, such as `de` or `de-DE`.")]
+pub struct ParseLocaleError;
+
+impl FromStr for Locale {
+    type Err = ParseLocaleError;
+
+    fn from_str(tag: &str) -> Result<Self, Self::Err> {
+        Self::new(tag).ok_or(ParseLocaleError)
+    }
+}
+
+impl TryFrom<&str> for Locale {
+    type Error = ParseLocaleError;
+
+    fn try_from(tag: &str) -> Result<Self, Self::Error> {
+        tag.parse()
+    }
+}
+
+impl TryFrom<String> for Locale {
+    type Error = ParseLocaleError;
+
+    fn try_from(tag: String) -> Result<Self, Self::Error> {
+        tag.parse()
+    }
+}
+
+impl From<Locale> for String {
+    fn from(locale: Locale) -> Self {
+        locale.to_string()
+    }
+}
+
+/// Returns whether the code is a two or three letter ISO 639 language code.
+fn is_language_code(code: &str) -> bool {
+    (2..=3).contains(&code.len()) && code.chars().all(|char| char.is_ascii_alphabetic())
+}
+
+/// Returns whether the code is a two letter ISO 3166-1 or three digit UN M49 region code.
+/// Unlike scripts, which are four letters.
+pub(crate) fn is_region_code(code: &str) -> bool {
+    (code.len() == 2 && code.chars().all(|char| char.is_ascii_alphabetic()))
+        || (code.len() == 3 && code.chars().all(|char| char.is_ascii_digit()))
+}
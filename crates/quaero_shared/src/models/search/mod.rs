@@ -1,15 +1,36 @@
+mod cache_status;
+pub use cache_status::*;
+
 mod date_time_range_preset;
 pub use date_time_range_preset::*;
 
 mod date_time_range;
 pub use date_time_range::*;
 
+mod locale;
+pub use locale::*;
+
 mod safe_search;
 pub use safe_search::*;
 
+mod rank_fusion;
+pub use rank_fusion::*;
+
+mod score_explanation;
+pub use score_explanation::*;
+
+mod search_category;
+pub use search_category::*;
+
 mod search_error;
 pub use search_error::*;
 
+mod search_event;
+pub use search_event::*;
+
+mod search_query;
+pub use search_query::*;
+
 mod search_options;
 pub use search_options::*;
 
@@ -19,5 +40,8 @@ pub use search_response::*;
 mod search_result_with_metadata;
 pub use search_result_with_metadata::*;
 
+mod search_result_kind;
+pub use search_result_kind::*;
+
 mod search_result;
 pub use search_result::*;
@@ -0,0 +1,57 @@
+/// The default `k` constant used by Reciprocal Rank Fusion, as suggested by the original paper.
+pub const DEFAULT_RRF_K: f32 = 60.;
+
+/// Controls how the rank each engine gave a search result is combined with its lexical score.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RankFusion {
+    /// Ranks search results by how relevant their contents are to the query,
+    /// scaled by the weight of the engines which returned them and how many of them agree.
+    Lexical,
+
+    /// Reciprocal Rank Fusion. Each engine which returned a search result contributes
+    /// `weight / (k + rank)` to its score. The lexical score is only used to break ties.
+    ReciprocalRank {
+        /// Dampens the impact of highly ranked results. Higher values flatten the curve.
+        k: f32,
+    },
+
+    /// Blends the normalised Reciprocal Rank Fusion score with the normalised lexical score.
+    /// The lexical score is scaled by the mean weight of the engines which returned the result.
+    Weighted {
+        /// Dampens the impact of highly ranked results. Higher values flatten the curve.
+        k: f32,
+
+        /// How much the rank score contributes to the final score (between `0.0` and `1.0`).
+        /// The remainder is contributed by the lexical score.
+        rank_weight: f32,
+    },
+}
+
+impl RankFusion {
+    /// Reciprocal Rank Fusion with the default `k` constant.
+    pub fn reciprocal_rank() -> Self {
+        Self::ReciprocalRank { k: DEFAULT_RRF_K }
+    }
+
+    /// Weighted Reciprocal Rank Fusion with the default `k` constant.
+    pub fn weighted(rank_weight: f32) -> Self {
+        Self::Weighted {
+            k: DEFAULT_RRF_K,
+            rank_weight: rank_weight.clamp(0., 1.),
+        }
+    }
+}
+
+/// Weighted Reciprocal Rank Fusion with an even split between the rank and lexical scores.
+///
+/// A search result several engines agree on normally stays ahead of one a single engine
+/// ranked much lower, but not always. For example, a result in the top three on Google,
+/// Bing and Brave has roughly 0.7 more normalised rank score than one only Mojeek returned
+/// ninth, so it falls behind if the Mojeek result's normalised lexical score is more than
+/// 0.7 higher than its own. Use a `rank_weight` of at least `0.6` to rule that case out,
+/// or [RankFusion::reciprocal_rank] for the lexical score to never outweigh the engines.
+impl Default for RankFusion {
+    fn default() -> Self {
+        Self::weighted(0.5)
+    }
+}
@@ -1,5 +1,12 @@
 /// A filtering scale which controls how strictly explicit search results are blocked.
-#[derive(Clone, Default)]
+///
+/// Serialized in lowercase, such as `"moderate"`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum SafeSearch {
     /// No explicit results are blocked.
     Off = 0,
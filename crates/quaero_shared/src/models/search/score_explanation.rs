@@ -0,0 +1,72 @@
+use crate::models::engine::EngineId;
+
+/// A breakdown of how a search result's relevance score was worked out,
+/// attached to each result when [SearchOptions::explain](crate::models::search::SearchOptions::explain) is set.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScoreExplanation {
+    /// Each engine which returned the search result.
+    pub engines: Vec<EngineContribution>,
+
+    /// How relevant the contents of the search result are to the query, according to the ranker.
+    pub lexical_score: f32,
+
+    /// How much each query token contributed to the lexical score.
+    /// Empty if the ranker can't break its scores down by token.
+    pub tokens: Vec<TokenContribution>,
+
+    /// Multipliers applied while fusing the engine ranks with the lexical score.
+    pub adjustments: Vec<ScoreAdjustment>,
+
+    /// The relevance score after rank fusion, before any score refiners ran.
+    pub fused_score: f32,
+
+    /// The raw score each stage (starting at 0) of the score refiner pipeline gave the search result.
+    pub refined_scores: Vec<(usize, f32)>,
+
+    /// The final relevance score.
+    pub relevance_score: f32,
+}
+
+/// How an engine contributed to a search result's score.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EngineContribution {
+    /// The engine which returned the search result.
+    pub engine_id: EngineId,
+
+    /// The position (starting at 0) the engine returned the search result at.
+    pub rank: usize,
+
+    /// How much the engine was trusted at the time of the search.
+    pub weight: f32,
+
+    /// The engine's share of the Reciprocal Rank Fusion score,
+    /// or `None` if rank fusion wasn't used.
+    pub rank_score: Option<f32>,
+}
+
+/// How a query token contributed to a search result's lexical score.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TokenContribution {
+    /// The query token.
+    pub token: String,
+
+    /// The query token it was added as a synonym of, if it wasn't in the query itself.
+    pub synonym_of: Option<String>,
+
+    /// How much it added to the lexical score.
+    pub score: f32,
+}
+
+/// A multiplier applied to part of a search result's score.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScoreAdjustment {
+    /// What the adjustment was for, such as `consensus`.
+    pub reason: String,
+
+    /// The multiplier. Values above `1.0` are boosts and values below it are penalties.
+    pub factor: f32,
+}
@@ -0,0 +1,35 @@
+/// The vertical a search is made in, which decides the kind of search results returned.
+///
+/// Serialized in lowercase, such as `"news"`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum SearchCategory {
+    /// General web pages.
+    #[default]
+    Web,
+
+    /// News articles.
+    News,
+
+    /// Images.
+    Images,
+
+    /// Videos.
+    Videos,
+}
+
+impl SearchCategory {
+    /// Converts to a lowercase string ("web", "news", "images" and "videos").
+    pub fn as_lowercase_string(&self) -> &'static str {
+        match self {
+            Self::Web => "web",
+            Self::News => "news",
+            Self::Images => "images",
+            Self::Videos => "videos",
+        }
+    }
+}
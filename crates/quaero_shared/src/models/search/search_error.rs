@@ -1,7 +1,14 @@
 use thiserror::Error;
 
 /// Potential errors when making a search.
-#[derive(Debug, Error)]
+///
+/// Serialized as its [kind](SearchError::kind), such as `"captcha"`.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum SearchError {
     /// The underlying request has failed.
     #[error("The underlying request has failed.")]
@@ -37,4 +44,34 @@ pub enum SearchError {
     /// Could not fetch results within the allowed time limit.
     #[error("Could not fetch results within the allowed time limit.")]
     Timeout,
+
+    /// The engine was skipped as it has been temporarily disabled after repeatedly
+    /// being blocked or timing out.
+    #[error(
+        "The engine has been temporarily disabled after repeatedly being blocked or timing out."
+    )]
+    TemporarilyDisabled,
+
+    /// The engine was skipped as it is over its rate limit.
+    #[error("The engine was skipped as it is over its rate limit.")]
+    RateLimited,
+}
+
+impl SearchError {
+    /// Returns a short, stable name for the kind of error (such as `captcha`),
+    /// suitable for use as a metric label.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SearchError::RequestFailed => "request_failed",
+            SearchError::NoResponseText => "no_response_text",
+            SearchError::NoResultsFound => "no_results_found",
+            SearchError::Blocked => "blocked",
+            SearchError::Captcha => "captcha",
+            SearchError::SafeSearchRestriction => "safe_search_restriction",
+            SearchError::Unknown => "unknown",
+            SearchError::Timeout => "timeout",
+            SearchError::TemporarilyDisabled => "temporarily_disabled",
+            SearchError::RateLimited => "rate_limited",
+        }
+    }
 }
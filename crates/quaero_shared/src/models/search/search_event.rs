@@ -0,0 +1,22 @@
+use crate::models::{
+    engine::EngineId,
+    search::{SearchError, SearchResponse, SearchResult, SearchResultWithMetadata},
+};
+
+/// An incremental update emitted while a streamed search is in progress.
+#[derive(Debug)]
+pub enum SearchEvent {
+    /// An engine has started fetching its results.
+    EngineStarted(EngineId),
+
+    /// An engine has finished, either with its results (in page order)
+    /// or with the reason it failed to fetch them.
+    EngineFinished(EngineId, Result<Box<[SearchResult]>, SearchError>),
+
+    /// The merged results of every engine which has finished so far, ranked by relevance.
+    /// The score refiner is not applied to snapshots.
+    Snapshot(Box<[SearchResultWithMetadata]>),
+
+    /// The search has finished. This is the same response a non-streamed search would return.
+    Finished(SearchResponse),
+}
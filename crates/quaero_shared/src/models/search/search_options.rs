@@ -1,18 +1,107 @@
-use std::sync::Arc;
+use std::{ops::Range, sync::Arc, time::Duration};
 
-use crate::models::search::{DateTimeRange, SafeSearch};
+use crate::models::{
+    engine::{EngineId, EngineSelector},
+    search::{DateTimeRange, Locale, SafeSearch, SearchCategory, locale::is_region_code},
+};
 
-#[derive(Default)]
+/// The maximum amount of pages each engine can fetch in a single search.
+pub const MAX_PAGE_SPAN: usize = 10;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 /// Options for customizing a search query.
+///
+/// Any fields missing when deserializing are left as their default.
 pub struct SearchOptions {
     /// The page number each engine will fetch data from.
     pub page_num: usize,
 
+    /// How many consecutive pages (starting at `page_num`) each engine will fetch.
+    /// A span of `0` is treated as `1`.
+    pub page_span: usize,
+
+    /// How many results each engine should fetch. Takes precedence over `page_span`,
+    /// with the amount of pages worked out from how many results each engine returns per page.
+    pub target_results: Option<usize>,
+
+    /// The vertical to search in, such as news or images.
+    pub category: SearchCategory,
+
     /// How explicit the search results are allowed to be.
     pub safe_search: SafeSearch,
 
     /// Restricts results to ones created between the specified range.
     pub date_time_range: Option<DateTimeRange>,
+
+    /// The language (and optionally the region) search results should be in.
+    /// Also decides which stop words are used when ranking results.
+    pub locale: Option<Locale>,
+
+    /// The ISO 3166-1 code of the region search results should be for,
+    /// overriding the locale's region.
+    pub region: Option<String>,
+
+    /// Attaches a [ScoreExplanation](crate::models::search::ScoreExplanation)
+    /// to each search result, detailing how its relevance score was worked out.
+    pub explain: bool,
+
+    /// Only queries the selected engines, or every engine if `None`.
+    pub include_engines: Option<Vec<EngineSelector>>,
+
+    /// Never queries the selected engines, even if they are included.
+    pub exclude_engines: Vec<EngineSelector>,
+
+    /// The maximum amount of time each engine has to fetch its results,
+    /// overriding the timeouts the quaero instance was configured with.
+    /// Serialized in milliseconds.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::serde_formats::option_millis")
+    )]
+    pub timeout: Option<Duration>,
+
+    /// Whether the query is expanded with synonyms when ranking results.
+    /// Only has an effect with the `synonyms` feature.
+    pub synonyms: bool,
+
+    /// Whether the score refiners rerank the results.
+    pub refine: bool,
+
+    /// How long the score refiners have to refine the results of this search, in total,
+    /// overriding the budget the quaero instance was configured with.
+    /// Serialized in milliseconds.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::serde_formats::option_millis")
+    )]
+    pub refine_budget: Option<Duration>,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            page_num: 0,
+            page_span: 0,
+            target_results: None,
+            category: SearchCategory::default(),
+            safe_search: SafeSearch::default(),
+            date_time_range: None,
+            locale: None,
+            region: None,
+            explain: false,
+            include_engines: None,
+            exclude_engines: Vec::new(),
+            timeout: None,
+            synonyms: true,
+            refine: true,
+            refine_budget: None,
+        }
+    }
 }
 
 impl SearchOptions {
@@ -22,12 +111,71 @@ impl SearchOptions {
         self
     }
 
+    /// Sets how many consecutive pages (starting at the page number) each engine will fetch.
+    pub fn page_span(mut self, page_span: usize) -> Self {
+        self.page_span = page_span;
+        self
+    }
+
+    /// Sets how many results each engine should fetch, fetching as many pages as needed.
+    pub fn target_results(mut self, target_results: usize) -> Self {
+        self.target_results = Some(target_results);
+        self
+    }
+
+    /// Returns the page numbers an engine should fetch, given how many results it returns per page.
+    pub fn page_range(&self, results_per_page: usize) -> Range<usize> {
+        let page_span = match self.target_results {
+            Some(target_results) => target_results.div_ceil(results_per_page.max(1)),
+            None => self.page_span,
+        }
+        .clamp(1, MAX_PAGE_SPAN);
+
+        self.page_num..self.page_num + page_span
+    }
+
+    /// Sets the vertical to search in, such as news or images.
+    pub fn category(mut self, category: SearchCategory) -> Self {
+        self.category = category;
+        self
+    }
+
     /// Sets the safe search for the search query.
     pub fn safe_search(mut self, safe_search: impl Into<SafeSearch>) -> Self {
         self.safe_search = safe_search.into();
         self
     }
 
+    /// Sets the language (and optionally the region) search results should be in, such as `de-DE`.
+    /// Invalid language tags are ignored, see [Locale::new].
+    pub fn locale(mut self, tag: impl AsRef<str>) -> Self {
+        self.locale = Locale::new(tag.as_ref());
+        self
+    }
+
+    /// Sets the region search results should be for, such as `DE`.
+    /// Anything other than two letters or three digits is ignored.
+    pub fn region(mut self, region: impl AsRef<str>) -> Self {
+        let region = region.as_ref().trim();
+        self.region = is_region_code(region).then(|| region.to_uppercase());
+        self
+    }
+
+    /// Returns the lowercase ISO 639-1 language code search results should be in.
+    pub fn language_code(&self) -> Option<&str> {
+        self.locale.as_ref().map(Locale::language)
+    }
+
+    /// Returns the uppercase ISO 3166-1 region code search results should be for,
+    /// falling back to the locale's region.
+    pub fn region_code(&self) -> Option<&str> {
+        // The region can be set directly (or deserialized), so it's validated again.
+        self.region
+            .as_deref()
+            .filter(|region| is_region_code(region))
+            .or_else(|| self.locale.as_ref().and_then(Locale::region))
+    }
+
     /// Sets the date time range for the search query.
     /// Engines which don't support custom ranges will pick it's nearest supported preset instead.
     pub fn date_time_range(mut self, range: impl Into<DateTimeRange>) -> Self {
@@ -35,6 +183,70 @@ impl SearchOptions {
         self
     }
 
+    /// Sets whether each search result should explain how its relevance score was worked out.
+    pub fn explain(mut self, explain: bool) -> Self {
+        self.explain = explain;
+        self
+    }
+
+    /// Only queries the selected engines (by name or identifier), such as `["google", "brave"]`.
+    pub fn include_engines(
+        mut self,
+        engines: impl IntoIterator<Item = impl Into<EngineSelector>>,
+    ) -> Self {
+        self.include_engines = Some(engines.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Never queries the selected engines (by name or identifier).
+    pub fn exclude_engines(
+        mut self,
+        engines: impl IntoIterator<Item = impl Into<EngineSelector>>,
+    ) -> Self {
+        self.exclude_engines
+            .extend(engines.into_iter().map(Into::into));
+        self
+    }
+
+    /// Returns whether the engine with the specified identifier should be queried.
+    pub fn includes_engine(&self, engine_id: &EngineId) -> bool {
+        let is_included = self.include_engines.as_ref().is_none_or(|include_engines| {
+            include_engines
+                .iter()
+                .any(|selector| selector.matches(engine_id))
+        });
+
+        is_included
+            && !self
+                .exclude_engines
+                .iter()
+                .any(|selector| selector.matches(engine_id))
+    }
+
+    /// Sets the maximum amount of time each engine has to fetch its results for this search.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets whether the query is expanded with synonyms when ranking results (defaults to `true`).
+    pub fn synonyms(mut self, synonyms: bool) -> Self {
+        self.synonyms = synonyms;
+        self
+    }
+
+    /// Sets whether the score refiners rerank the results (defaults to `true`).
+    pub fn refine(mut self, refine: bool) -> Self {
+        self.refine = refine;
+        self
+    }
+
+    /// Sets how long the score refiners have to refine the results of this search, in total.
+    pub fn refine_budget(mut self, refine_budget: Duration) -> Self {
+        self.refine_budget = Some(refine_budget);
+        self
+    }
+
     /// Convenience helper to wrap the SearchOptions with an Arc.
     pub fn into_arc(self) -> Arc<Self> {
         Arc::new(self)
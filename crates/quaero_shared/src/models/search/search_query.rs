@@ -0,0 +1,418 @@
+use std::fmt::{self, Display};
+
+use crate::models::search::SearchResult;
+
+/// A search query parsed into its terms and operators, such as
+/// `site:docs.rs "async trait" -tokio filetype:pdf`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    /// The clauses of the query, all of which need to be satisfied.
+    pub clauses: Vec<QueryClause>,
+}
+
+/// A single clause of a [SearchQuery].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryClause {
+    /// The term should be matched.
+    Include(QueryTerm),
+
+    /// The term must not be matched (`-term`).
+    Exclude(QueryTerm),
+
+    /// At least one of the terms should be matched (`a OR b`).
+    AnyOf(Vec<QueryTerm>),
+}
+
+/// A single term of a [SearchQuery].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryTerm {
+    /// A plain word.
+    Word(String),
+
+    /// An exact phrase (`"async trait"`).
+    Phrase(String),
+
+    /// Restricts results to a host and its subdomains (`site:docs.rs`).
+    Site(String),
+
+    /// Restricts results to a file extension (`filetype:pdf`).
+    FileType(String),
+
+    /// Restricts results to ones with the text in their title (`intitle:rust`).
+    InTitle(String),
+}
+
+/// The operators an engine understands. Operators an engine doesn't understand
+/// are left out of the query sent to it and are enforced locally instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryOperators {
+    /// Exact phrases (`"async trait"`).
+    pub phrase: bool,
+
+    /// Exclusions (`-tokio`).
+    pub exclude: bool,
+
+    /// Host restrictions (`site:docs.rs`).
+    pub site: bool,
+
+    /// File extension restrictions (`filetype:pdf`).
+    pub file_type: bool,
+
+    /// Title restrictions (`intitle:rust`).
+    pub in_title: bool,
+
+    /// Alternatives (`a OR b`).
+    pub or: bool,
+}
+
+impl QueryOperators {
+    /// Every operator is understood.
+    pub const ALL: Self = Self {
+        phrase: true,
+        exclude: true,
+        site: true,
+        file_type: true,
+        in_title: true,
+        or: true,
+    };
+
+    /// No operators are understood, so only plain words are sent.
+    pub const NONE: Self = Self {
+        phrase: false,
+        exclude: false,
+        site: false,
+        file_type: false,
+        in_title: false,
+        or: false,
+    };
+
+    /// Returns whether the term can be sent to the engine as is.
+    pub fn supports_term(&self, term: &QueryTerm) -> bool {
+        match term {
+            QueryTerm::Word(_) => true,
+            QueryTerm::Phrase(_) => self.phrase,
+            QueryTerm::Site(_) => self.site,
+            QueryTerm::FileType(_) => self.file_type,
+            QueryTerm::InTitle(_) => self.in_title,
+        }
+    }
+
+    /// Returns whether the clause can be sent to the engine as is.
+    pub fn supports(&self, clause: &QueryClause) -> bool {
+        match clause {
+            QueryClause::Include(term) => self.supports_term(term),
+            QueryClause::Exclude(term) => self.exclude && self.supports_term(term),
+            QueryClause::AnyOf(terms) => {
+                self.or && terms.iter().all(|term| self.supports_term(term))
+            }
+        }
+    }
+}
+
+/// How an engine spells each operator.
+#[derive(Debug, Clone, Copy)]
+pub struct QuerySyntax {
+    /// The prefix for host restrictions.
+    pub site: &'static str,
+
+    /// The prefix for file extension restrictions.
+    pub file_type: &'static str,
+
+    /// The prefix for title restrictions.
+    pub in_title: &'static str,
+
+    /// The separator between alternatives.
+    pub or: &'static str,
+}
+
+impl Default for QuerySyntax {
+    fn default() -> Self {
+        Self {
+            site: "site:",
+            file_type: "filetype:",
+            in_title: "intitle:",
+            or: "OR",
+        }
+    }
+}
+
+impl SearchQuery {
+    /// Parses a query typed by a user. Anything which isn't a recognised operator
+    /// is kept as a plain word, so parsing never fails.
+    pub fn parse(query: &str) -> Self {
+        let mut clauses: Vec<QueryClause> = Vec::new();
+        let mut pending_or = false;
+
+        for token in tokenize(query) {
+            if token == "OR" || token == "|" {
+                pending_or = !clauses.is_empty();
+                continue;
+            }
+
+            let (is_excluded, token) = match token.strip_prefix('-') {
+                Some(rest) if !rest.is_empty() => (true, rest),
+                _ => (false, token.as_str()),
+            };
+
+            let Some(term) = parse_term(token) else {
+                continue;
+            };
+
+            if is_excluded {
+                clauses.push(QueryClause::Exclude(term));
+                pending_or = false;
+                continue;
+            }
+
+            let previous = match pending_or {
+                true => clauses.pop(),
+                false => None,
+            };
+            pending_or = false;
+
+            let clause = match previous {
+                Some(QueryClause::Include(previous)) => QueryClause::AnyOf(vec![previous, term]),
+                Some(QueryClause::AnyOf(mut terms)) => {
+                    terms.push(term);
+                    QueryClause::AnyOf(terms)
+                }
+                Some(previous) => {
+                    // Exclusions can't be alternatives, so `OR` is ignored after them.
+                    clauses.push(previous);
+                    QueryClause::Include(term)
+                }
+                None => QueryClause::Include(term),
+            };
+
+            clauses.push(clause);
+        }
+
+        Self { clauses }
+    }
+
+    /// Renders the query, leaving out any operators the engine doesn't understand.
+    /// Words from unsupported phrases, titles and alternatives are still sent as plain words.
+    pub fn render(&self, operators: QueryOperators, syntax: &QuerySyntax) -> String {
+        let mut parts: Vec<String> = Vec::with_capacity(self.clauses.len());
+
+        for clause in &self.clauses {
+            if operators.supports(clause) {
+                parts.push(render_clause(clause, syntax));
+                continue;
+            }
+
+            let terms: &[QueryTerm] = match clause {
+                QueryClause::Include(term) => std::slice::from_ref(term),
+                QueryClause::AnyOf(terms) => terms,
+                QueryClause::Exclude(_) => continue,
+            };
+
+            for term in terms {
+                match term {
+                    QueryTerm::Word(text) | QueryTerm::Phrase(text) | QueryTerm::InTitle(text) => {
+                        parts.push(text.clone())
+                    }
+                    QueryTerm::Site(_) | QueryTerm::FileType(_) => {}
+                }
+            }
+        }
+
+        parts.join(" ")
+    }
+
+    /// Returns the text the search results should be relevant to,
+    /// leaving out exclusions and host or file extension restrictions.
+    pub fn text(&self) -> String {
+        let terms = self.clauses.iter().flat_map(|clause| match clause {
+            QueryClause::Include(term) => std::slice::from_ref(term),
+            QueryClause::AnyOf(terms) => terms.as_slice(),
+            QueryClause::Exclude(_) => &[],
+        });
+
+        terms
+            .filter_map(|term| match term {
+                QueryTerm::Word(text) | QueryTerm::Phrase(text) | QueryTerm::InTitle(text) => {
+                    Some(text.as_str())
+                }
+                QueryTerm::Site(_) | QueryTerm::FileType(_) => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Returns whether the search result satisfies the clauses the engine didn't understand.
+    ///
+    /// Results are only checked against their title, url and summary. The page itself may
+    /// contain words and phrases which are missing from them, so those are only enforced
+    /// when they are excluded and do show up.
+    pub fn matches(&self, search_result: &SearchResult, operators: QueryOperators) -> bool {
+        self.clauses
+            .iter()
+            .filter(|clause| !operators.supports(clause))
+            .all(|clause| match clause {
+                QueryClause::Include(term) => term.matches(search_result).unwrap_or(true),
+                QueryClause::Exclude(term) => !term.matches(search_result).unwrap_or(false),
+                QueryClause::AnyOf(terms) => terms
+                    .iter()
+                    .any(|term| term.matches(search_result).unwrap_or(true)),
+            })
+    }
+}
+
+impl Display for SearchQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(QueryOperators::ALL, &QuerySyntax::default()))
+    }
+}
+
+impl QueryTerm {
+    /// Lowercases the term's text, leaving its kind untouched.
+    pub fn make_lowercase(&mut self) {
+        let (QueryTerm::Word(text)
+        | QueryTerm::Phrase(text)
+        | QueryTerm::Site(text)
+        | QueryTerm::FileType(text)
+        | QueryTerm::InTitle(text)) = self;
+
+        *text = text.to_lowercase();
+    }
+
+    /// Returns whether the search result matches the term, or `None` if it can't be told
+    /// locally. Words and phrases only ever match, as they could be on the page but missing
+    /// from the search result's title and summary.
+    pub fn matches(&self, search_result: &SearchResult) -> Option<bool> {
+        let SearchResult {
+            title,
+            url,
+            summary,
+            ..
+        } = search_result;
+
+        match self {
+            QueryTerm::Word(word) => {
+                (contains_word(title, word) || contains_word(summary, word)).then_some(true)
+            }
+            QueryTerm::Phrase(phrase) => (contains_ignore_case(title, phrase)
+                || contains_ignore_case(summary, phrase))
+            .then_some(true),
+            QueryTerm::InTitle(text) => Some(contains_ignore_case(title, text)),
+            QueryTerm::Site(site) => {
+                let host = url_host(url);
+                let site = site.to_lowercase();
+
+                Some(host == site || host.ends_with(&format!(".{site}")))
+            }
+            QueryTerm::FileType(file_type) => {
+                let path = url.split(['?', '#']).next().unwrap_or_default();
+
+                Some(
+                    path.to_lowercase()
+                        .ends_with(&format!(".{}", file_type.to_lowercase())),
+                )
+            }
+        }
+    }
+}
+
+fn render_clause(clause: &QueryClause, syntax: &QuerySyntax) -> String {
+    match clause {
+        QueryClause::Include(term) => render_term(term, syntax),
+        QueryClause::Exclude(term) => format!("-{}", render_term(term, syntax)),
+        QueryClause::AnyOf(terms) => terms
+            .iter()
+            .map(|term| render_term(term, syntax))
+            .collect::<Vec<_>>()
+            .join(&format!(" {} ", syntax.or)),
+    }
+}
+
+fn render_term(term: &QueryTerm, syntax: &QuerySyntax) -> String {
+    match term {
+        QueryTerm::Word(word) => word.clone(),
+        QueryTerm::Phrase(phrase) => format!("\"{phrase}\""),
+        QueryTerm::Site(site) => format!("{}{site}", syntax.site),
+        QueryTerm::FileType(file_type) => format!("{}{file_type}", syntax.file_type),
+        QueryTerm::InTitle(text) if text.contains(' ') => {
+            format!("{}\"{text}\"", syntax.in_title)
+        }
+        QueryTerm::InTitle(text) => format!("{}{text}", syntax.in_title),
+    }
+}
+
+fn parse_term(token: &str) -> Option<QueryTerm> {
+    if token.starts_with('"') {
+        let phrase = token.trim_matches('"').trim();
+        return (!phrase.is_empty()).then(|| QueryTerm::Phrase(phrase.to_string()));
+    }
+
+    let Some((operator, value)) = token.split_once(':') else {
+        return Some(QueryTerm::Word(token.to_string()));
+    };
+
+    let value = value.trim_matches('"');
+
+    if value.is_empty() {
+        return Some(QueryTerm::Word(token.to_string()));
+    }
+
+    match operator.to_lowercase().as_str() {
+        "site" => Some(QueryTerm::Site(value.to_string())),
+        "filetype" | "ext" => Some(QueryTerm::FileType(
+            value.trim_start_matches('.').to_string(),
+        )),
+        "intitle" => Some(QueryTerm::InTitle(value.to_string())),
+        _ => Some(QueryTerm::Word(token.to_string())),
+    }
+}
+
+/// Splits a query by whitespace, keeping quoted text (and its quotes) together.
+fn tokenize(query: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut in_quotes = false;
+
+    for char in query.chars() {
+        if char == '"' {
+            in_quotes = !in_quotes;
+        }
+
+        if char.is_whitespace() && !in_quotes {
+            if !token.is_empty() {
+                tokens.push(std::mem::take(&mut token));
+            }
+            continue;
+        }
+
+        token.push(char);
+    }
+
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+
+    tokens
+}
+
+fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+/// Returns whether the word appears on its own in the text, ignoring case.
+/// Words with symbols in them (such as `c++`) are matched anywhere in the text.
+fn contains_word(text: &str, word: &str) -> bool {
+    if !word.chars().all(char::is_alphanumeric) {
+        return contains_ignore_case(text, word);
+    }
+
+    text.split(|char: char| !char.is_alphanumeric())
+        .any(|text_word| text_word.to_lowercase() == word.to_lowercase())
+}
+
+fn url_host(url: &str) -> String {
+    let url = url.split_once("://").map_or(url, |(_protocol, rest)| rest);
+    let host = url.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = host.rsplit_once('@').map_or(host, |(_user, host)| host);
+    let host = host.split(':').next().unwrap_or_default();
+
+    host.to_lowercase()
+}
@@ -1,15 +1,26 @@
 use crate::models::{
     engine::EngineId,
-    search::{SearchError, SearchResultWithMetadata},
+    search::{CacheStatus, SearchError, SearchResultWithMetadata},
 };
 
 /// The response gotten at the end of the search query.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SearchResponse {
     /// The aggregated search results across all engines.
     pub results: Box<[SearchResultWithMetadata]>,
 
     /// The statuses for each individual engine which specifies
     /// if any issues occured when fetching results.
+    ///
+    /// Serialized as `[{ "engine": "Google:V1StGXR8_Z", "error": null }, ..]`,
+    /// with `error` being the [kind](SearchError::kind) of error, if any.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::serde_formats::engine_statuses")
+    )]
     pub statuses: Vec<(EngineId, Result<(), SearchError>)>,
+
+    /// Whether the response was fetched from the engines or served from a cache.
+    pub cache_status: CacheStatus,
 }
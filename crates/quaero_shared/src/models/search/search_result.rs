@@ -1,7 +1,8 @@
-use crate::models::sanitized_url::SanitizedUrl;
+use crate::models::{sanitized_url::SanitizedUrl, search::SearchResultKind};
 
 /// Contains data pertaining to an individual search result fetched from a particular engine.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SearchResult {
     /// The title of the search result.
     pub title: String,
@@ -11,6 +12,9 @@ pub struct SearchResult {
 
     /// The short description / summary typically found underneath the search result.
     pub summary: String,
+
+    /// The kind of the search result, along with any data specific to its category.
+    pub kind: SearchResultKind,
 }
 
 impl SearchResult {
@@ -33,10 +37,17 @@ impl SearchResult {
                 title,
                 url: sanitized_url.to_string(),
                 summary: summary,
+                kind: SearchResultKind::Web,
             },
         )
     }
 
+    /// Sets the kind of the search result.
+    pub fn with_kind(mut self, kind: SearchResultKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
     /// Concatenates the title and summary into a snippet.
     pub fn snippet(&self) -> String {
         format!("{} | {}", self.title, self.summary)
@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+/// The kind of a search result, along with any data specific to its category.
+///
+/// Serialized with a `type` field alongside the data, such as `{ "type": "image", "width": 640, .. }`.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename_all = "snake_case")
+)]
+pub enum SearchResultKind {
+    /// A general web page.
+    #[default]
+    Web,
+
+    /// A news article.
+    News(NewsDetails),
+
+    /// An image.
+    Image(ImageDetails),
+
+    /// A video.
+    Video(VideoDetails),
+}
+
+/// Data specific to news articles.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NewsDetails {
+    /// The name of the publication the article is from.
+    pub source: Option<String>,
+
+    /// When the article was published.
+    pub published_at: Option<DateTime<Utc>>,
+
+    /// The url of the article's thumbnail.
+    pub thumbnail_url: Option<String>,
+}
+
+/// Data specific to images. The search result's url points to the full size image.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImageDetails {
+    /// The url of a smaller version of the image.
+    pub thumbnail_url: Option<String>,
+
+    /// The url of the page the image was found on.
+    pub source_page_url: Option<String>,
+
+    /// The width of the full size image, in pixels.
+    pub width: Option<u32>,
+
+    /// The height of the full size image, in pixels.
+    pub height: Option<u32>,
+}
+
+/// Data specific to videos.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VideoDetails {
+    /// How long the video is. Serialized in milliseconds.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::serde_formats::option_millis")
+    )]
+    pub duration: Option<Duration>,
+
+    /// The url of the video's thumbnail.
+    pub thumbnail_url: Option<String>,
+
+    /// The name of the site or channel which published the video.
+    pub publisher: Option<String>,
+
+    /// When the video was published.
+    pub published_at: Option<DateTime<Utc>>,
+}
@@ -1,27 +1,110 @@
 use smallvec::SmallVec;
 
-use crate::models::{engine::EngineId, search::SearchResult};
+use crate::models::{
+    engine::EngineId,
+    search::{ScoreExplanation, SearchResult},
+};
 
 /// A search result with extra metadata.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SearchResultWithMetadata {
     /// The underlying search result.
     pub search_result: SearchResult,
 
+    /// The strict form of the search result's url, as produced by
+    /// [SanitizedUrl::to_strict_string](crate::models::sanitized_url::SanitizedUrl::to_strict_string),
+    /// which is used to deduplicate it.
+    pub url_key: String,
+
+    /// The urls of near-duplicates (such as syndicated copies or mobile
+    /// variants) which were collapsed into this search result.
+    pub also_at: Vec<String>,
+
     /// The engines this search result was found in.
     pub engines: SmallVec<[EngineId; 1]>,
 
+    /// The position (starting at 0) each engine returned this search result at.
+    pub ranks: SmallVec<[(EngineId, usize); 1]>,
+
+    /// How relevant the contents of this search result are to the original query.
+    pub lexical_score: f32,
+
     /// How relevant this search is to the original query.
     pub relevance_score: f32,
+
+    /// The raw score each stage (starting at 0) of the score refiner pipeline gave this
+    /// search result. Stages which didn't score it, such as when it was outside of their
+    /// top results, are left out.
+    pub refined_scores: SmallVec<[(usize, f32); 1]>,
+
+    /// How the relevance score was worked out, if the search was made
+    /// with [SearchOptions::explain](crate::models::search::SearchOptions::explain).
+    pub explanation: Option<Box<ScoreExplanation>>,
 }
 
 impl SearchResultWithMetadata {
     /// Creates a new [SearchResultWithMetadata].
-    pub fn new(search_result: SearchResult, engine_id: &EngineId) -> Self {
+    pub fn new(
+        url_key: String,
+        search_result: SearchResult,
+        engine_id: &EngineId,
+        rank: usize,
+    ) -> Self {
         Self {
             search_result,
+            url_key,
+            also_at: Vec::new(),
             engines: SmallVec::from([engine_id.clone()]),
+            ranks: SmallVec::from([(engine_id.clone(), rank)]),
+            lexical_score: 0.,
             relevance_score: 0.,
+            refined_scores: SmallVec::new(),
+            explanation: None,
+        }
+    }
+
+    /// Merges the engines and ranks of a duplicate search result into this one.
+    /// If an engine returned the same result more than once then its best rank is kept.
+    pub fn merge_engines(&mut self, other: Self) {
+        for engine_id in other.engines {
+            if !self.engines.contains(&engine_id) {
+                self.engines.push(engine_id);
+            }
         }
+
+        for (engine_id, rank) in other.ranks {
+            match self
+                .ranks
+                .iter_mut()
+                .find(|(existing_id, _)| *existing_id == engine_id)
+            {
+                Some((_, existing_rank)) => *existing_rank = (*existing_rank).min(rank),
+                None => self.ranks.push((engine_id, rank)),
+            }
+        }
+    }
+
+    /// Collapses a near-duplicate search result at a different url into this one,
+    /// keeping its url (and any it had collapsed) as alternates.
+    pub fn merge_near_duplicate(&mut self, mut other: Self) {
+        let urls = std::iter::once(std::mem::take(&mut other.search_result.url))
+            .chain(std::mem::take(&mut other.also_at));
+
+        for url in urls {
+            if url != self.search_result.url && !self.also_at.contains(&url) {
+                self.also_at.push(url);
+            }
+        }
+
+        self.merge_engines(other);
+    }
+
+    /// Returns the rank the specified engine gave this search result, if it returned it.
+    pub fn rank_for(&self, engine_id: &EngineId) -> Option<usize> {
+        self.ranks
+            .iter()
+            .find(|(existing_id, _)| existing_id == engine_id)
+            .map(|(_, rank)| *rank)
     }
 }
@@ -0,0 +1,168 @@
+use std::{
+    fmt::Write,
+    time::{Duration, SystemTime},
+};
+
+use crate::models::{
+    engine::EngineSelector,
+    search::{CacheStatus, QueryClause, QueryTerm, SearchOptions, SearchQuery, SearchResponse},
+};
+
+/// A trait for defining storage for previously fetched search responses.
+#[async_trait::async_trait]
+pub trait SearchCache: Send + Sync {
+    /// Returns the response stored under the key along with how fresh it is.
+    /// Expired responses should not be returned.
+    async fn get(&self, key: &str) -> Option<(SearchResponse, CacheStatus)>;
+
+    /// Stores a response under the key, replacing any existing response.
+    async fn insert(&self, key: &str, response: &SearchResponse);
+}
+
+/// Determines how long cached responses stay fresh for.
+#[derive(Debug, Clone, Copy)]
+pub struct CacheExpiry {
+    /// How long a response is served as a [CacheStatus::Hit] for.
+    pub ttl: Duration,
+
+    /// How long a response is served as a [CacheStatus::Stale] for after its ttl has passed.
+    pub stale_while_revalidate: Option<Duration>,
+}
+
+impl CacheExpiry {
+    /// Creates a new expiry without stale-while-revalidate.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            stale_while_revalidate: None,
+        }
+    }
+
+    /// Returns the freshness of a response created at the specified time,
+    /// or `None` if it has expired.
+    pub fn status(&self, created_at: SystemTime) -> Option<CacheStatus> {
+        // Responses from the future (the clock went backwards) are treated as brand new.
+        let age = created_at.elapsed().unwrap_or_default();
+
+        if age <= self.ttl {
+            return Some(CacheStatus::Hit);
+        }
+
+        match self.stale_while_revalidate {
+            Some(stale_window) if age <= self.ttl + stale_window => Some(CacheStatus::Stale),
+            _ => None,
+        }
+    }
+}
+
+/// Creates a cache key from a normalised query and the options it was searched with.
+pub fn cache_key(query: &str, options: &SearchOptions) -> String {
+    let SearchOptions {
+        page_num,
+        page_span,
+        target_results,
+        category,
+        safe_search,
+        date_time_range,
+        locale: _,
+        region: _,
+        explain,
+        include_engines,
+        exclude_engines,
+        timeout,
+        synonyms,
+        refine,
+        refine_budget,
+    } = options;
+
+    // The key is built from the parsed query so that only operators are case sensitive,
+    // e.g. `rust OR go` and `rust or go` are different searches.
+    let mut parsed_query = SearchQuery::parse(query);
+    for clause in &mut parsed_query.clauses {
+        match clause {
+            QueryClause::Include(term) | QueryClause::Exclude(term) => term.make_lowercase(),
+            QueryClause::AnyOf(terms) => terms.iter_mut().for_each(QueryTerm::make_lowercase),
+        }
+    }
+
+    let mut key = parsed_query.to_string();
+
+    let _ = write!(
+        key,
+        "\u{1f}page={page_num}\u{1f}category={}\u{1f}safe={}",
+        category.as_lowercase_string(),
+        safe_search.as_lowercase_string()
+    );
+
+    match target_results {
+        Some(target_results) => {
+            let _ = write!(key, "\u{1f}results={target_results}");
+        }
+        None if *page_span > 1 => {
+            let _ = write!(key, "\u{1f}span={page_span}");
+        }
+        None => {}
+    }
+
+    if let Some(language) = options.language_code() {
+        let _ = write!(key, "\u{1f}lang={language}");
+    }
+
+    if let Some(region) = options.region_code() {
+        let _ = write!(key, "\u{1f}region={region}");
+    }
+
+    if *explain {
+        key.push_str("\u{1f}explain");
+    }
+
+    if let Some(include_engines) = include_engines {
+        let _ = write!(key, "\u{1f}engines={}", selectors_key(include_engines));
+    }
+
+    if !exclude_engines.is_empty() {
+        let _ = write!(key, "\u{1f}exclude={}", selectors_key(exclude_engines));
+    }
+
+    if let Some(timeout) = timeout {
+        let _ = write!(key, "\u{1f}timeout={}", timeout.as_millis());
+    }
+
+    if !synonyms {
+        key.push_str("\u{1f}no_synonyms");
+    }
+
+    if !refine {
+        key.push_str("\u{1f}no_refine");
+    } else if let Some(refine_budget) = refine_budget {
+        // The budget decides which refiner stages get to run.
+        let _ = write!(key, "\u{1f}refine_budget={}", refine_budget.as_millis());
+    }
+
+    if let Some(date_time_range) = date_time_range {
+        // Ranges are rounded to the minute as presets are relative to the current time.
+        let _ = write!(
+            key,
+            "\u{1f}range={}-{}",
+            date_time_range.start.timestamp() / 60,
+            date_time_range.end.timestamp() / 60
+        );
+    }
+
+    key
+}
+
+/// Creates an order independent key for a list of engine selectors.
+fn selectors_key(selectors: &[EngineSelector]) -> String {
+    let mut selectors: Vec<String> = selectors
+        .iter()
+        .map(|selector| match selector {
+            EngineSelector::Id(engine_id) => format!("{}#{}", engine_id.name(), engine_id.id()),
+            EngineSelector::Name(name) => name.to_lowercase(),
+        })
+        .collect();
+
+    selectors.sort_unstable();
+    selectors.dedup();
+    selectors.join(",")
+}
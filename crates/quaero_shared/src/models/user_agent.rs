@@ -21,6 +21,27 @@ impl<'a> UserAgent<'a> {
             USER_AGENTS_ALLOWED_NO_JS[rng.random_range(0..USER_AGENTS_ALLOWED_NO_JS.len())];
         Self(Cow::Borrowed(chosen))
     }
+
+    /// Creates a new random user agent from the same pool as the specified user agent,
+    /// but which is different to it. Returns `None` if the user agent isn't a known one.
+    pub fn random_other_than(user_agent: &str) -> Option<Self> {
+        let pool: &[&'static str] = if USER_AGENTS.contains(&user_agent) {
+            &USER_AGENTS
+        } else if USER_AGENTS_ALLOWED_NO_JS.contains(&user_agent) {
+            &USER_AGENTS_ALLOWED_NO_JS
+        } else {
+            return None;
+        };
+
+        let mut rng = rand::rng();
+        let chosen_idx = rng.random_range(0..pool.len() - 1);
+        let chosen = pool
+            .iter()
+            .filter(|this| **this != user_agent)
+            .nth(chosen_idx)?;
+
+        Some(Self(Cow::Borrowed(chosen)))
+    }
 }
 
 impl<'a> Into<HeaderValue> for UserAgent<'a> {
@@ -5,3 +5,6 @@ pub use normalize_path::*;
 
 mod string_clip;
 pub use string_clip::*;
+
+#[cfg(feature = "serde")]
+pub(crate) mod serde_formats;
@@ -0,0 +1,64 @@
+//! Serde representations for fields whose types don't have a stable one of their own.
+
+/// Serializes an optional [Duration](std::time::Duration) as a whole number of milliseconds.
+pub(crate) mod option_millis {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        duration
+            .map(|duration| duration.as_millis() as u64)
+            .serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_millis))
+    }
+}
+
+/// Serializes the status of each engine as `{ "engine": "Google:V1StGXR8_Z", "error": null }`,
+/// with `error` being the [kind](crate::models::search::SearchError::kind) of error, if any.
+pub(crate) mod engine_statuses {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::models::{engine::EngineId, search::SearchError};
+
+    type EngineStatuses = Vec<(EngineId, Result<(), SearchError>)>;
+
+    #[derive(Serialize)]
+    struct EngineStatusRef<'a> {
+        engine: &'a EngineId,
+        error: Option<&'a SearchError>,
+    }
+
+    #[derive(Deserialize)]
+    struct EngineStatus {
+        engine: EngineId,
+        error: Option<SearchError>,
+    }
+
+    pub(crate) fn serialize<S: Serializer>(
+        statuses: &[(EngineId, Result<(), SearchError>)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(statuses.iter().map(|(engine, status)| EngineStatusRef {
+            engine,
+            error: status.as_ref().err(),
+        }))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<EngineStatuses, D::Error> {
+        Ok(Vec::<EngineStatus>::deserialize(deserializer)?
+            .into_iter()
+            .map(|EngineStatus { engine, error }| (engine, error.map_or(Ok(()), Err)))
+            .collect())
+    }
+}
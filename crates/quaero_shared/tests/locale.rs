@@ -0,0 +1,48 @@
+use quaero_shared::models::search::{Locale, SearchOptions};
+
+#[test]
+fn valid_locales() {
+    let locale = Locale::new("de-DE").unwrap();
+    assert_eq!(locale.language(), "de");
+    assert_eq!(locale.region(), Some("DE"));
+
+    let locale = Locale::new("zh_Hant_TW").unwrap();
+    assert_eq!(locale.language(), "zh");
+    assert_eq!(locale.region(), Some("TW"));
+
+    let locale = Locale::new("fil").unwrap();
+    assert_eq!(locale.language(), "fil");
+    assert_eq!(locale.region(), None);
+
+    assert_eq!(Locale::new("es-419").unwrap().region(), Some("419"));
+}
+
+#[test]
+fn invalid_locales() {
+    for tag in ["", "e", "engl", "en&x=1", "e1", "ü-DE"] {
+        assert_eq!(Locale::new(tag), None, "{tag:?} should be rejected");
+    }
+
+    // Invalid regions are dropped rather than rejecting the whole locale.
+    assert_eq!(Locale::new("en-x&y=1").unwrap().region(), None);
+}
+
+#[test]
+fn invalid_options_are_ignored() {
+    let options = SearchOptions::default()
+        .locale("en&x=1")
+        .region("x&foo=bar");
+
+    assert_eq!(options.language_code(), None);
+    assert_eq!(options.region_code(), None);
+
+    let options = SearchOptions::default().locale("en-GB").region("de");
+    assert_eq!(options.language_code(), Some("en"));
+    assert_eq!(options.region_code(), Some("DE"));
+
+    // Regions which are set directly are validated when they're read,
+    // falling back to the locale's region.
+    let mut options = SearchOptions::default().locale("en-GB");
+    options.region = Some("x&foo=bar".to_string());
+    assert_eq!(options.region_code(), Some("GB"));
+}
@@ -0,0 +1,245 @@
+use quaero_shared::models::{
+    search::{
+        QueryClause, QueryOperators, QuerySyntax, QueryTerm, SearchOptions, SearchQuery,
+        SearchResult, SearchResultKind,
+    },
+    search_cache::cache_key,
+};
+
+fn word(word: &str) -> QueryTerm {
+    QueryTerm::Word(word.to_string())
+}
+
+fn phrase(phrase: &str) -> QueryTerm {
+    QueryTerm::Phrase(phrase.to_string())
+}
+
+#[test]
+fn phrases() {
+    let query = SearchQuery::parse(r#"rust "async trait""#);
+
+    assert_eq!(
+        query.clauses,
+        [
+            QueryClause::Include(word("rust")),
+            QueryClause::Include(phrase("async trait"))
+        ]
+    );
+
+    // Empty phrases are dropped.
+    assert_eq!(SearchQuery::parse(r#"rust """#).clauses.len(), 1);
+}
+
+#[test]
+fn unbalanced_quotes() {
+    // The rest of the query is treated as the phrase.
+    let query = SearchQuery::parse(r#"rust "async trait"#);
+
+    assert_eq!(
+        query.clauses,
+        [
+            QueryClause::Include(word("rust")),
+            QueryClause::Include(phrase("async trait"))
+        ]
+    );
+    assert_eq!(query.to_string(), r#"rust "async trait""#);
+}
+
+#[test]
+fn exclusions() {
+    let query = SearchQuery::parse(r#"rust -tokio -"async std" - -site:reddit.com"#);
+
+    assert_eq!(
+        query.clauses,
+        [
+            QueryClause::Include(word("rust")),
+            QueryClause::Exclude(word("tokio")),
+            QueryClause::Exclude(phrase("async std")),
+            // A lone dash is just a word.
+            QueryClause::Include(word("-")),
+            QueryClause::Exclude(QueryTerm::Site("reddit.com".to_string())),
+        ]
+    );
+}
+
+#[test]
+fn field_operators() {
+    let query =
+        SearchQuery::parse(r#"SITE:docs.rs filetype:.pdf ext:txt intitle:"rust book" site:"#);
+
+    assert_eq!(
+        query.clauses,
+        [
+            QueryClause::Include(QueryTerm::Site("docs.rs".to_string())),
+            QueryClause::Include(QueryTerm::FileType("pdf".to_string())),
+            QueryClause::Include(QueryTerm::FileType("txt".to_string())),
+            QueryClause::Include(QueryTerm::InTitle("rust book".to_string())),
+            // Operators without a value, and unknown operators, are kept as words.
+            QueryClause::Include(word("site:")),
+        ]
+    );
+
+    assert_eq!(
+        SearchQuery::parse("std::mem").clauses,
+        [QueryClause::Include(word("std::mem"))]
+    );
+}
+
+#[test]
+fn alternatives() {
+    let query = SearchQuery::parse(r#"rust OR go | "zig lang" -c OR java"#);
+
+    assert_eq!(
+        query.clauses,
+        [
+            QueryClause::AnyOf(vec![word("rust"), word("go"), phrase("zig lang")]),
+            // Exclusions can't be alternatives.
+            QueryClause::Exclude(word("c")),
+            QueryClause::Include(word("java")),
+        ]
+    );
+
+    // Only the uppercase `OR` is an operator.
+    assert_eq!(
+        SearchQuery::parse("rust or go").clauses,
+        [
+            QueryClause::Include(word("rust")),
+            QueryClause::Include(word("or")),
+            QueryClause::Include(word("go")),
+        ]
+    );
+
+    // A leading or trailing `OR` is ignored.
+    assert_eq!(
+        SearchQuery::parse("OR rust OR").clauses,
+        [QueryClause::Include(word("rust"))]
+    );
+}
+
+#[test]
+fn render_round_trip() {
+    let queries = [
+        "rust",
+        r#"site:docs.rs "async trait" -tokio filetype:pdf"#,
+        r#"intitle:"rust book" intitle:ferris"#,
+        r#"rust OR go OR "zig lang" -site:reddit.com"#,
+    ];
+
+    for query in queries {
+        let parsed = SearchQuery::parse(query);
+        assert_eq!(parsed.to_string(), query);
+        assert_eq!(SearchQuery::parse(&parsed.to_string()), parsed);
+    }
+
+    // `|` is rendered as the engine's own separator.
+    assert_eq!(SearchQuery::parse("rust | go").to_string(), "rust OR go");
+}
+
+#[test]
+fn render_unsupported_operators() {
+    let query = SearchQuery::parse(r#"site:docs.rs "async trait" -tokio rust OR go"#);
+
+    assert_eq!(
+        query.render(QueryOperators::NONE, &QuerySyntax::default()),
+        "async trait rust go"
+    );
+    assert_eq!(query.text(), "async trait rust go");
+}
+
+fn search_result(title: &str, url: &str, summary: &str) -> SearchResult {
+    SearchResult {
+        title: title.to_string(),
+        url: url.to_string(),
+        summary: summary.to_string(),
+        kind: SearchResultKind::Web,
+    }
+}
+
+#[test]
+fn enforces_excluded_words_locally() {
+    let query = SearchQuery::parse("rust async -tokio");
+    let matches = |result: &SearchResult| query.matches(result, QueryOperators::NONE);
+
+    assert!(!matches(&search_result(
+        "Tokio tutorial",
+        "https://tokio.rs/tokio/tutorial",
+        "Learn async rust."
+    )));
+    assert!(!matches(&search_result(
+        "Async rust",
+        "https://example.com",
+        "Runtimes such as tokio, async-std and smol."
+    )));
+
+    // Only whole words are excluded, and they may still be elsewhere on the page.
+    assert!(matches(&search_result(
+        "Async rust with tokiox",
+        "https://example.com/tokio",
+        "A runtime comparison."
+    )));
+
+    // Engines which understand exclusions are trusted to have applied them.
+    assert!(query.matches(
+        &search_result("Tokio tutorial", "https://tokio.rs", ""),
+        QueryOperators::ALL
+    ));
+}
+
+#[test]
+fn doesnt_enforce_included_phrases_locally() {
+    let query = SearchQuery::parse(r#""async trait" -"blocking io""#);
+    let matches = |result: &SearchResult| query.matches(result, QueryOperators::NONE);
+
+    // The phrase may be on the page even though it isn't in the snippet.
+    assert!(matches(&search_result(
+        "Rust traits",
+        "https://doc.rust-lang.org/book/ch10-02-traits.html",
+        "Defining shared behavior."
+    )));
+    assert!(matches(&search_result(
+        "Async Trait",
+        "https://docs.rs/async-trait",
+        ""
+    )));
+
+    // Excluded phrases which show up are enforced.
+    assert!(!matches(&search_result(
+        "Async trait",
+        "https://docs.rs/async-trait",
+        "Avoid Blocking IO in async code."
+    )));
+}
+
+#[test]
+fn cache_keys_keep_operators() {
+    let options = SearchOptions::default();
+
+    assert_eq!(
+        cache_key("Rust  Async", &options),
+        cache_key("rust async", &options)
+    );
+    assert_eq!(
+        cache_key("rust OR go", &options),
+        cache_key("Rust OR Go", &options)
+    );
+    assert_ne!(
+        cache_key("rust OR go", &options),
+        cache_key("rust or go", &options)
+    );
+}
+
+#[test]
+fn cache_keys_include_refine_budget() {
+    use std::time::Duration;
+
+    let options = SearchOptions::default();
+    let with_budget = SearchOptions::default().refine_budget(Duration::from_millis(500));
+
+    assert_ne!(cache_key("rust", &options), cache_key("rust", &with_budget));
+
+    // The budget doesn't matter when the results aren't refined.
+    assert_eq!(
+        cache_key("rust", &options.refine(false)),
+        cache_key("rust", &with_budget.refine(false))
+    );
+}
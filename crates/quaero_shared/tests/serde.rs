@@ -0,0 +1,219 @@
+#![cfg(feature = "serde")]
+
+use std::time::Duration;
+
+use chrono::{TimeZone, Utc};
+use quaero_shared::models::{
+    engine::{EngineId, EngineSelector},
+    search::{
+        CacheStatus, DateTimeRange, ImageDetails, SafeSearch, SearchCategory, SearchError,
+        SearchOptions, SearchResponse, SearchResult, SearchResultKind, SearchResultWithMetadata,
+    },
+};
+use serde_json::{Value, json};
+
+fn engine_id(engine_id: &str) -> EngineId {
+    engine_id.parse().unwrap()
+}
+
+/// Checks that the value serializes to the expected json, and that it deserializes back into the same json.
+fn assert_round_trip<T: serde::Serialize + serde::de::DeserializeOwned>(
+    value: &T,
+    expected: Value,
+) {
+    let serialized = serde_json::to_value(value).unwrap();
+    assert_eq!(serialized, expected);
+
+    let deserialized: T = serde_json::from_value(serialized).unwrap();
+    assert_eq!(serde_json::to_value(&deserialized).unwrap(), expected);
+}
+
+#[test]
+fn engine_id_string() {
+    let google = engine_id("Google:V1StGXR8_Z");
+    assert_eq!(google.name(), "Google");
+    assert_eq!(google.id(), "V1StGXR8_Z");
+
+    assert_round_trip(&google, json!("Google:V1StGXR8_Z"));
+
+    // Names can contain colons.
+    assert_eq!(engine_id("Some:Engine:abc").name(), "Some:Engine");
+
+    assert!(serde_json::from_value::<EngineId>(json!("Google")).is_err());
+    assert!(serde_json::from_value::<EngineId>(json!("Google:")).is_err());
+}
+
+#[test]
+fn search_error_kinds() {
+    let errors = [
+        SearchError::RequestFailed,
+        SearchError::NoResponseText,
+        SearchError::NoResultsFound,
+        SearchError::Blocked,
+        SearchError::Captcha,
+        SearchError::SafeSearchRestriction,
+        SearchError::Unknown,
+        SearchError::Timeout,
+        SearchError::TemporarilyDisabled,
+        SearchError::RateLimited,
+    ];
+
+    for error in errors {
+        assert_round_trip(&error, json!(error.kind()));
+    }
+}
+
+#[test]
+fn search_options() {
+    let options = SearchOptions::default()
+        .page_num(2)
+        .page_span(3)
+        .category(SearchCategory::News)
+        .safe_search(SafeSearch::Strict)
+        .date_time_range(DateTimeRange::new(
+            Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2025, 2, 1, 12, 30, 0).unwrap(),
+        ))
+        .locale("de-DE")
+        .region("at")
+        .explain(true)
+        .include_engines([
+            EngineSelector::from("google"),
+            EngineSelector::from(engine_id("Brave:abc")),
+        ])
+        .exclude_engines(["yandex"])
+        .timeout(Duration::from_millis(2500))
+        .synonyms(false)
+        .refine(false);
+
+    assert_round_trip(
+        &options,
+        json!({
+            "page_num": 2,
+            "page_span": 3,
+            "target_results": null,
+            "category": "news",
+            "safe_search": "strict",
+            "date_time_range": {
+                "start": "2025-01-01T00:00:00Z",
+                "end": "2025-02-01T12:30:00Z"
+            },
+            "locale": "de-DE",
+            "region": "AT",
+            "explain": true,
+            "include_engines": [{ "name": "google" }, { "id": "Brave:abc" }],
+            "exclude_engines": [{ "name": "yandex" }],
+            "timeout": 2500,
+            "synonyms": false,
+            "refine": false,
+            "refine_budget": null
+        }),
+    );
+}
+
+#[test]
+fn search_options_defaults() {
+    let options: SearchOptions =
+        serde_json::from_value(json!({ "page_num": 1, "safe_search": "off" })).unwrap();
+
+    assert_eq!(options.page_num, 1);
+    assert_eq!(options.safe_search, SafeSearch::Off);
+    assert_eq!(options.category, SearchCategory::Web);
+    assert_eq!(options.include_engines, None);
+    assert_eq!(options.timeout, None);
+    assert!(options.synonyms);
+    assert!(options.refine);
+    assert_eq!(options.refine_budget, None);
+}
+
+#[test]
+fn invalid_locales_are_rejected() {
+    assert!(serde_json::from_value::<SearchOptions>(json!({ "locale": "en&x=1" })).is_err());
+}
+
+#[test]
+fn search_response() {
+    let google = engine_id("Google:V1StGXR8_Z");
+    let bing = engine_id("Bing:Uakgb_J5m9");
+
+    let search_result = SearchResult {
+        title: "Ferris".to_string(),
+        url: "https://rustacean.net/ferris.png".to_string(),
+        summary: "The unofficial mascot of Rust.".to_string(),
+        kind: SearchResultKind::Image(ImageDetails {
+            thumbnail_url: None,
+            source_page_url: Some("https://rustacean.net".to_string()),
+            width: Some(640),
+            height: Some(480),
+        }),
+    };
+
+    let mut result = SearchResultWithMetadata::new(
+        "rustacean.net/ferris.png".to_string(),
+        search_result,
+        &google,
+        0,
+    );
+    result.lexical_score = 0.5;
+    result.relevance_score = 1.25;
+    result.refined_scores.push((0, 0.75));
+
+    let response = SearchResponse {
+        results: Box::new([result]),
+        statuses: vec![(google, Ok(())), (bing, Err(SearchError::Captcha))],
+        cache_status: CacheStatus::Hit,
+    };
+
+    assert_round_trip(
+        &response,
+        json!({
+            "results": [{
+                "search_result": {
+                    "title": "Ferris",
+                    "url": "https://rustacean.net/ferris.png",
+                    "summary": "The unofficial mascot of Rust.",
+                    "kind": {
+                        "type": "image",
+                        "thumbnail_url": null,
+                        "source_page_url": "https://rustacean.net",
+                        "width": 640,
+                        "height": 480
+                    }
+                },
+                "url_key": "rustacean.net/ferris.png",
+                "also_at": [],
+                "engines": ["Google:V1StGXR8_Z"],
+                "ranks": [["Google:V1StGXR8_Z", 0]],
+                "lexical_score": 0.5,
+                "relevance_score": 1.25,
+                "refined_scores": [[0, 0.75]],
+                "explanation": null
+            }],
+            "statuses": [
+                { "engine": "Google:V1StGXR8_Z", "error": null },
+                { "engine": "Bing:Uakgb_J5m9", "error": "captcha" }
+            ],
+            "cache_status": "hit"
+        }),
+    );
+}
+
+#[test]
+fn search_result_kinds() {
+    let web = SearchResult {
+        title: "Rust".to_string(),
+        url: "https://www.rust-lang.org".to_string(),
+        summary: String::new(),
+        kind: SearchResultKind::Web,
+    };
+
+    assert_round_trip(
+        &web,
+        json!({
+            "title": "Rust",
+            "url": "https://www.rust-lang.org",
+            "summary": "",
+            "kind": { "type": "web" }
+        }),
+    );
+}
@@ -0,0 +1,451 @@
+use anyhttp::HttpClient;
+use http::{
+    HeaderValue, Request, StatusCode,
+    header::{ACCEPT_LANGUAGE, USER_AGENT},
+};
+use std::{
+    ops::Range,
+    pin::pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::task::JoinSet;
+use tracing::{Instrument, error, field::Empty, info, info_span, warn};
+
+use quaero_shared::models::{
+    engine::{Engine, EngineId},
+    search::{SearchError, SearchOptions, SearchResult},
+    user_agent::UserAgent,
+};
+
+use crate::{
+    client_pool::ClientPool, health::HealthTracker, middleware::Middleware,
+    rate_limit::RateLimiter, retry::RetryPolicy,
+};
+
+pub type EngineResults = Result<Vec<(String, SearchResult)>, SearchError>;
+
+/// An engine's results paired with their rank on the engine, counting from its first page.
+pub type RankedResults = Result<Vec<(usize, String, SearchResult)>, SearchError>;
+
+/// Everything needed to fetch the search results of a single engine.
+pub struct EngineFetch<C: HttpClient + 'static> {
+    pub engine_id: EngineId,
+    pub engine: Arc<dyn Engine>,
+    pub engine_name: String,
+    pub clients: Arc<ClientPool<C>>,
+    pub encoded_query: Arc<String>,
+    pub options: Arc<SearchOptions>,
+    /// The middleware requests and responses pass through, outermost first.
+    pub middleware: Arc<[Arc<dyn Middleware>]>,
+    pub rate_limiter: Arc<RateLimiter>,
+}
+
+impl<C: HttpClient + 'static> EngineFetch<C> {
+    /// Fetches multiple pages of the engine's results concurrently, concatenating them in page order.
+    /// The first page's request should already have been taken from the engine's rate limit.
+    /// Pages which failed still take up a full page of ranks, so later results keep their positions.
+    /// Fails only if every page failed, with the error of the earliest page.
+    pub async fn fetch_pages(
+        &self,
+        page_nums: Range<usize>,
+        retry_policy: &Arc<RetryPolicy>,
+        health: &Arc<HealthTracker>,
+        deadline: Instant,
+    ) -> RankedResults {
+        if page_nums.len() <= 1 {
+            let results = match page_nums.start == self.options.page_num {
+                true => {
+                    self.fetch_with_retries(retry_policy, health, deadline)
+                        .await
+                }
+                false => {
+                    self.with_page_num(page_nums.start)
+                        .fetch_with_retries(retry_policy, health, deadline)
+                        .await
+                }
+            };
+
+            return results.map(|results| ranked(0, results).collect());
+        }
+
+        let mut pages: JoinSet<(usize, EngineResults)> = JoinSet::new();
+
+        for (page_idx, page_num) in page_nums.enumerate() {
+            let fetch = self.with_page_num(page_num);
+            let (retry_policy, health) = (retry_policy.clone(), health.clone());
+
+            pages.spawn(async move {
+                if page_idx > 0 && !fetch.rate_limiter.acquire(&fetch.engine_id, deadline).await {
+                    info!(
+                        "[{}] Skipping page {} as the engine is over its rate limit.",
+                        fetch.engine_name, page_num
+                    );
+                    return (page_idx, Err(SearchError::RateLimited));
+                }
+
+                let results = fetch
+                    .fetch_with_retries(&retry_policy, &health, deadline)
+                    .await;
+                (page_idx, results)
+            });
+        }
+
+        let mut page_results = pages.join_all().await;
+        page_results.sort_unstable_by_key(|(page_idx, _)| *page_idx);
+
+        let results_per_page = self.engine.category_results_per_page(self.options.category);
+        let mut results = Vec::new();
+        let mut next_rank = 0;
+        let mut first_error = None;
+
+        for (_, page_result) in page_results {
+            match page_result {
+                Ok(page_result) => {
+                    // Pages can be longer than expected, so ranks are never reused.
+                    let page_len = page_result.len().max(results_per_page);
+                    results.extend(ranked(next_rank, page_result));
+                    next_rank += page_len;
+                }
+                Err(search_error) => {
+                    first_error.get_or_insert(search_error);
+                    next_rank += results_per_page;
+                }
+            }
+        }
+
+        match first_error {
+            Some(search_error) if results.is_empty() => Err(search_error),
+            _ => Ok(results),
+        }
+    }
+
+    /// Creates a copy of this fetch which requests a different page.
+    fn with_page_num(&self, page_num: usize) -> Self {
+        let mut options = SearchOptions::clone(&self.options);
+        options.page_num = page_num;
+
+        Self {
+            engine_id: self.engine_id.clone(),
+            engine: self.engine.clone(),
+            engine_name: self.engine_name.clone(),
+            clients: self.clients.clone(),
+            encoded_query: self.encoded_query.clone(),
+            options: Arc::new(options),
+            middleware: self.middleware.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+        }
+    }
+
+    /// Fetches the engine's results, retrying transient failures
+    /// as long as there is enough time left before the deadline.
+    pub async fn fetch_with_retries(
+        &self,
+        retry_policy: &RetryPolicy,
+        health: &HealthTracker,
+        deadline: Instant,
+    ) -> EngineResults {
+        let hedge_delay = retry_policy.hedging.and_then(|hedging| {
+            health.latency_percentile(&self.engine_id, hedging.percentile, hedging.min_samples)
+        });
+
+        let mut attempt = 1;
+
+        loop {
+            let started_at = Instant::now();
+            let results = self.fetch_hedged(hedge_delay).await;
+
+            if results.is_ok() {
+                health.record_latency(&self.engine_id, started_at.elapsed());
+            }
+
+            let Err(search_error) = &results else {
+                return results;
+            };
+
+            if attempt >= retry_policy.max_attempts || !retry_policy.is_retryable(search_error) {
+                return results;
+            }
+
+            let backoff = retry_policy.backoff(attempt);
+
+            if Instant::now() + backoff >= deadline {
+                return results;
+            }
+
+            warn!(
+                "[{}] Retrying in {:?} (attempt {}/{}).",
+                self.engine_name,
+                backoff,
+                attempt + 1,
+                retry_policy.max_attempts
+            );
+
+            tokio::time::sleep(backoff).await;
+            attempt += 1;
+
+            if !self.rate_limiter.acquire(&self.engine_id, deadline).await {
+                info!(
+                    "[{}] Not retrying as the engine is over its rate limit.",
+                    self.engine_name
+                );
+                return results;
+            }
+        }
+    }
+
+    /// Fetches the engine's results. If a hedge delay is specified and the engine hasn't
+    /// answered by then, a second request with a different user agent is fired and
+    /// whichever succeeds first is used.
+    async fn fetch_hedged(&self, hedge_delay: Option<Duration>) -> EngineResults {
+        let request = self.build_request(None)?;
+
+        let Some(hedge_delay) = hedge_delay else {
+            return self.execute(request).await;
+        };
+
+        let first_user_agent = request.headers().get(USER_AGENT).cloned();
+
+        let mut first = pin!(self.execute(request));
+
+        tokio::select! {
+            results = &mut first => return results,
+            _ = tokio::time::sleep(hedge_delay) => {}
+        }
+
+        // Hedges are only worth firing if they don't have to wait for the rate limit.
+        if !self
+            .rate_limiter
+            .acquire(&self.engine_id, Instant::now())
+            .await
+        {
+            return first.await;
+        }
+
+        info!(
+            "[{}] No response after {:?}, firing a hedged request.",
+            self.engine_name, hedge_delay
+        );
+
+        let hedged_request = match self.build_request(first_user_agent.as_ref()) {
+            Ok(hedged_request) => hedged_request,
+            Err(_) => return first.await,
+        };
+
+        let mut second = pin!(self.execute(hedged_request));
+
+        tokio::select! {
+            results = &mut first => match results {
+                Ok(results) => Ok(results),
+                Err(_) => second.await,
+            },
+            results = &mut second => match results {
+                Ok(results) => Ok(results),
+                Err(_) => first.await,
+            },
+        }
+    }
+
+    /// Builds the request for the engine. If a user agent to avoid is specified,
+    /// a different one is used in its place.
+    fn build_request(
+        &self,
+        avoid_user_agent: Option<&HeaderValue>,
+    ) -> Result<Request<Vec<u8>>, SearchError> {
+        let _span = info_span!("build_url").entered();
+        let engine_name = &self.engine_name;
+
+        let request_url = match self
+            .engine
+            .url(self.encoded_query.as_ref(), self.options.as_ref())
+        {
+            Ok(request_url) => request_url,
+            Err(search_error) => {
+                error!(
+                    "[{}] Error when obtaining URL: {}",
+                    engine_name, search_error
+                );
+                return Err(search_error);
+            }
+        };
+
+        let mut request = match Request::get(request_url).body(vec![]) {
+            Ok(request) => request,
+            Err(err) => {
+                error!("[{}] Failed to build request: {:#?}", engine_name, err);
+                return Err(SearchError::RequestFailed);
+            }
+        };
+
+        self.engine
+            .headers(request.headers_mut(), self.options.as_ref());
+
+        // Engines which don't map the locale themselves are at least told the preferred language.
+        if !request.headers().contains_key(ACCEPT_LANGUAGE)
+            && let Some(locale) = &self.options.locale
+            && let Ok(accept_language) = HeaderValue::from_str(&locale.to_accept_language())
+        {
+            request
+                .headers_mut()
+                .insert(ACCEPT_LANGUAGE, accept_language);
+        }
+
+        if let Some(avoid_user_agent) = avoid_user_agent
+            && request.headers().get(USER_AGENT) == Some(avoid_user_agent)
+            && let Some(user_agent) = avoid_user_agent
+                .to_str()
+                .ok()
+                .and_then(UserAgent::random_other_than)
+        {
+            request.headers_mut().insert(USER_AGENT, user_agent.into());
+        }
+
+        Ok(request)
+    }
+
+    /// Executes the request through the pool's current client, rotating to
+    /// the next client if the engine blocked it or showed it a captcha.
+    async fn execute(&self, request: Request<Vec<u8>>) -> EngineResults {
+        let (client_idx, client) = self.clients.current();
+
+        let results = self.execute_with(client.as_ref(), request).await;
+
+        if matches!(results, Err(SearchError::Blocked | SearchError::Captcha))
+            && self.clients.rotate_from(client_idx)
+        {
+            warn!(
+                "[{}] Rotating to the next client in the pool.",
+                self.engine_name
+            );
+        }
+
+        results
+    }
+
+    /// Executes the request and parses the search results from the response.
+    async fn execute_with(&self, client: &C, mut request: Request<Vec<u8>>) -> EngineResults {
+        let (engine, engine_name) = (&self.engine, &self.engine_name);
+
+        for middleware in self.middleware.iter() {
+            if let Err(search_error) = middleware
+                .on_request(&self.engine_id, engine_name, &mut request)
+                .await
+            {
+                error!(
+                    "[{}] Request rejected by middleware: {}",
+                    engine_name, search_error
+                );
+                return Err(search_error);
+            }
+        }
+
+        let http_span = info_span!("http", status = Empty, bytes = Empty);
+
+        let response_result = client.execute(request).instrument(http_span.clone()).await;
+
+        let mut response = match response_result {
+            Ok(response) => response,
+            Err(err) => {
+                error!("[{}] Failed to fetch results: {:#?}", engine_name, err);
+                return Err(SearchError::RequestFailed);
+            }
+        };
+
+        for middleware in self.middleware.iter().rev() {
+            response = match middleware
+                .on_response(&self.engine_id, engine_name, response)
+                .await
+            {
+                Ok(response) => response,
+                Err(search_error) => {
+                    error!(
+                        "[{}] Response rejected by middleware: {}",
+                        engine_name, search_error
+                    );
+                    return Err(search_error);
+                }
+            };
+        }
+
+        http_span.record("status", response.status().as_u16());
+
+        if let Err(search_error) =
+            info_span!("validate").in_scope(|| engine.validate_response(&response))
+        {
+            error!("[{}] Failed pre-parse check: {}", engine_name, search_error);
+            return Err(search_error);
+        }
+
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            error!(
+                "[{}] Failed to fetch results: {}",
+                engine_name,
+                SearchError::Blocked
+            );
+            return Err(SearchError::Blocked);
+        }
+
+        let bytes = match response.bytes().instrument(http_span.clone()).await {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                error!(
+                    "[{}] Failed to parse response text: {:#?}",
+                    engine_name, err
+                );
+                return Err(SearchError::RequestFailed);
+            }
+        };
+
+        http_span.record("bytes", bytes.len());
+
+        let Ok(data) = str::from_utf8(&bytes) else {
+            error!("[{}] No response text was found.", engine_name);
+            return Err(SearchError::NoResponseText);
+        };
+        let data = data.to_string();
+
+        let (engine, category) = (engine.clone(), self.options.category);
+        let parse_span = info_span!("parse", results = Empty);
+        let results = tokio::task::spawn_blocking({
+            let parse_span = parse_span.clone();
+            move || parse_span.in_scope(|| engine.parse_category(data, category))
+        })
+        .await
+        .unwrap_or_else(|_| Err(SearchError::Unknown))
+        .and_then(|this| {
+            if this.len() == 0 {
+                Err(SearchError::NoResultsFound)
+            } else {
+                Ok(this)
+            }
+        });
+
+        let results = match results {
+            Ok(results) => results,
+            Err(search_error) => {
+                error!(
+                    "[{}] Failed to parse results: {}",
+                    engine_name, search_error
+                );
+                return Err(search_error);
+            }
+        };
+
+        parse_span.record("results", results.len());
+
+        info!("[{}] Successfully fetched search results!", engine_name);
+
+        Ok(results)
+    }
+}
+
+/// Pairs each of a page's results with its rank, starting from the rank of the page's first result.
+fn ranked(
+    first_rank: usize,
+    results: Vec<(String, SearchResult)>,
+) -> impl Iterator<Item = (usize, String, SearchResult)> {
+    results
+        .into_iter()
+        .enumerate()
+        .map(move |(idx, (url, result))| (first_rank + idx, url, result))
+}
@@ -0,0 +1,54 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex},
+};
+
+use stop_words::LANGUAGE;
+
+/// The stop words used to tokenize queries in a particular language.
+pub struct TextRules {
+    /// The ISO 639-1 code of the language.
+    pub language: &'static str,
+
+    /// Words which are too common to say anything about relevance.
+    pub stop_words: Box<[String]>,
+}
+
+impl TextRules {
+    /// Returns the rules for the specified language, falling back to English if it isn't supported.
+    pub fn for_language(language: Option<&str>) -> Arc<Self> {
+        let (language, stop_words_language) = match language.unwrap_or("en") {
+            "ar" => ("ar", LANGUAGE::Arabic),
+            "de" => ("de", LANGUAGE::German),
+            "es" => ("es", LANGUAGE::Spanish),
+            "fr" => ("fr", LANGUAGE::French),
+            "it" => ("it", LANGUAGE::Italian),
+            "ja" => ("ja", LANGUAGE::Japanese),
+            "ko" => ("ko", LANGUAGE::Korean),
+            "nl" => ("nl", LANGUAGE::Dutch),
+            "pl" => ("pl", LANGUAGE::Polish),
+            "pt" => ("pt", LANGUAGE::Portuguese),
+            "ru" => ("ru", LANGUAGE::Russian),
+            "sv" => ("sv", LANGUAGE::Swedish),
+            "tr" => ("tr", LANGUAGE::Turkish),
+            "zh" => ("zh", LANGUAGE::Chinese),
+            _ => ("en", LANGUAGE::English),
+        };
+
+        let mut cache = TEXT_RULES.lock().unwrap_or_else(|err| err.into_inner());
+
+        cache
+            .entry(language)
+            .or_insert_with(|| {
+                Arc::new(Self {
+                    language,
+                    stop_words: stop_words::get(stop_words_language).into_boxed_slice(),
+                })
+            })
+            .clone()
+    }
+}
+
+/// Rules are built lazily as stop word lists are fairly large.
+static TEXT_RULES: LazyLock<Mutex<HashMap<&'static str, Arc<TextRules>>>> =
+    LazyLock::new(Mutex::default);
@@ -1,193 +1,486 @@
 use anyhttp::HttpClient;
-use http::{Request, StatusCode};
-use keyword_extraction::tokenizer::Tokenizer;
+use async_stream::stream;
+use futures_core::Stream;
 use rayon::prelude::*;
 use std::{
-    collections::HashMap,
-    sync::{Arc, LazyLock},
+    collections::{HashMap, HashSet},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, Instant},
 };
 use tokio::task::JoinSet;
-use tracing::{Level, error, info, level_enabled};
+use tracing::{
+    Instrument, Level, Span, error, field::Empty, info, info_span, instrument, level_enabled,
+};
+
+mod fetch;
+
+mod language;
+use language::TextRules;
 
-mod update_relevance;
+use fetch::{EngineFetch, RankedResults};
+
+mod rank_fusion;
+use rank_fusion::{explain_fusion, fuse_scores};
 
 use quaero_shared::models::{
     engine::{EngineId, TaggedEngine},
-    score_refiner::ScoreRefiner,
-    search::{SearchError, SearchOptions, SearchResponse, SearchResult, SearchResultWithMetadata},
+    ranker::Ranker,
+    search::{
+        CacheStatus, QueryOperators, RankFusion, ScoreExplanation, SearchError, SearchEvent,
+        SearchOptions, SearchQuery, SearchResponse, SearchResult, SearchResultWithMetadata,
+    },
+    search_cache::cache_key,
+};
+
+use crate::{
+    Quaero, cache,
+    dedup::{NearDuplicates, collapse_near_duplicates},
+    engine_set::EngineSet,
+    ranking,
+    rate_limit::{self, OverBudget},
+    refine::refine_results,
 };
 
-use crate::{Quaero, aggregator::update_relevance::UpdateRelevance};
+type EngineOutcome = (EngineId, RankedResults);
+
+/// Overrides which engines are queried and which pages they are queried for.
+#[derive(Debug, Clone, Default)]
+pub struct SearchPlan {
+    /// The page each engine should start fetching from, instead of the options' page number.
+    pub page_cursors: HashMap<EngineId, usize>,
+
+    /// The engines which shouldn't be queried at all.
+    pub excluded_engines: HashSet<EngineId>,
+}
 
 #[inline(always)]
+#[instrument(
+    name = "search",
+    skip_all,
+    fields(query = query, category = options.category.as_lowercase_string())
+)]
 pub async fn aggregate_results<C: HttpClient + 'static, const N: usize>(
     quaero: &Quaero<C, N>,
     query: &str,
     options: Arc<SearchOptions>,
+    plan: &SearchPlan,
 ) -> SearchResponse {
     info!("Commencing Quaero search!");
 
-    let encoded_query = urlencoding::encode(query);
-    let encoded_query = Arc::new(encoded_query.to_string());
+    let parsed_query = Arc::new(SearchQuery::parse(query));
+    // The engines are read once so that they stay consistent for the duration of the search.
+    let engines = quaero.engines.load_full();
+
+    let mut aggregator = Aggregator::new(&parsed_query, &options, quaero, &engines);
+    let EngineTasks {
+        mut tasks, skipped, ..
+    } = spawn_engine_tasks(quaero, &engines, &parsed_query, options, plan);
+
+    for engine_id in skipped {
+        aggregator.merge(engine_id, Err(SearchError::TemporarilyDisabled));
+    }
+
+    while let Some(Ok((engine_id, engine_results))) = tasks.join_next().await {
+        aggregator.merge(engine_id, engine_results);
+    }
+
+    aggregator.finish(&parsed_query.text(), quaero).await
+}
+
+/// Same as [aggregate_results] but yields incremental events as each engine finishes.
+pub fn aggregate_results_stream<'a, C: HttpClient + 'static, const N: usize>(
+    quaero: &'a Quaero<C, N>,
+    query: String,
+    options: Arc<SearchOptions>,
+) -> impl Stream<Item = SearchEvent> + Send + 'a {
+    let span = info_span!(
+        "search",
+        query = query,
+        category = options.category.as_lowercase_string()
+    );
+
+    let stream = stream! {
+        info!("Commencing streamed Quaero search!");
+
+        let cache_key = quaero.cache.as_ref().map(|_| cache_key(&query, &options));
+
+        if let (Some(cache), Some(key)) = (&quaero.cache, &cache_key)
+            && let Some(response) = quaero.cached_response(cache.as_ref(), key, &query, &options).await
+        {
+            yield SearchEvent::Finished(response);
+            return;
+        }
+
+        let parsed_query = Arc::new(SearchQuery::parse(&query));
+        // The engines are read once so that they stay consistent for the duration of the search.
+        let engines = quaero.engines.load_full();
+
+        let mut aggregator = Aggregator::new(&parsed_query, &options, quaero, &engines);
+        let EngineTasks { mut tasks, started, skipped } = spawn_engine_tasks(quaero, &engines, &parsed_query, options, &SearchPlan::default());
+
+        for engine_id in started {
+            yield SearchEvent::EngineStarted(engine_id);
+        }
+
+
+        for engine_id in skipped {
+            aggregator.merge(engine_id.clone(), Err(SearchError::TemporarilyDisabled));
+            yield SearchEvent::EngineFinished(engine_id, Err(SearchError::TemporarilyDisabled));
+        }
+
+        while let Some(Ok((engine_id, engine_results))) = tasks.join_next().await {
+            let event_results = match &engine_results {
+                Ok(engine_results) => Ok(engine_results
+                    .iter()
+                    .map(|(_, _, result)| result.clone())
+                    .collect()),
+                Err(search_error) => Err(search_error.clone()),
+            };
+
+            aggregator.merge(engine_id.clone(), engine_results);
+
+            yield SearchEvent::EngineFinished(engine_id, event_results);
+            yield SearchEvent::Snapshot(aggregator.snapshot());
+        }
+
+        let response = aggregator.finish(&parsed_query.text(), quaero).await;
+
+        if let (Some(cache), Some(key)) = (&quaero.cache, &cache_key) {
+            cache::store(cache.as_ref(), key, &response).await;
+        }
+
+        yield SearchEvent::Finished(response);
+    };
+
+    InstrumentedStream {
+        stream: Box::pin(stream),
+        span,
+    }
+}
+
+/// A stream which enters its span whenever it's polled, like [Instrument] does for futures.
+struct InstrumentedStream<S> {
+    stream: Pin<Box<S>>,
+    span: Span,
+}
+
+impl<S: Stream> Stream for InstrumentedStream<S> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let _entered = this.span.enter();
+        this.stream.as_mut().poll_next(cx)
+    }
+}
+
+/// The tasks fetching each engine's results.
+struct EngineTasks {
+    tasks: JoinSet<EngineOutcome>,
 
-    let query_tokens = get_query_tokens(query);
+    /// The engines which are being fetched.
+    started: Vec<EngineId>,
 
-    let mut tasks: JoinSet<(EngineId, Result<Vec<(String, SearchResult)>, SearchError>)> =
-        JoinSet::new();
+    /// The engines which were skipped as they are temporarily disabled.
+    skipped: Vec<EngineId>,
+}
 
-    let engines = &quaero.engines;
-    let engines_len = engines.len();
+/// Spawns a task for each healthy engine which fetches its results within the allowed time limit.
+fn spawn_engine_tasks<C: HttpClient + 'static, const N: usize>(
+    quaero: &Quaero<C, N>,
+    engines: &EngineSet<N>,
+    query: &Arc<SearchQuery>,
+    options: Arc<SearchOptions>,
+    plan: &SearchPlan,
+) -> EngineTasks {
+    let mut tasks: JoinSet<EngineOutcome> = JoinSet::new();
 
-    let timeout_duration = quaero.timeout;
+    let mut started = Vec::with_capacity(engines.engines.len());
+    let mut skipped = Vec::new();
+
+    for TaggedEngine(engine_id, engine, _) in engines.enabled() {
+        if plan.excluded_engines.contains(engine_id) || !options.includes_engine(engine_id) {
+            continue;
+        }
 
-    for TaggedEngine(engine_id, engine) in engines {
         let engine_name = engine.name();
 
-        info!("[{}] Fetching search results...", engine_name);
+        if !engine.categories().contains(&options.category) {
+            info!(
+                "[{}] Skipping engine as it doesn't support {} searches.",
+                engine_name,
+                options.category.as_lowercase_string()
+            );
+            continue;
+        }
 
-        let (outer_engine_id, engine_id, engine) =
-            (engine_id.clone(), engine_id.clone(), engine.clone());
-        let client = quaero.client.clone();
-        let options = options.clone();
-        let encoded_query = encoded_query.clone();
-
-        tasks.spawn(async move {
-            let result = tokio::time::timeout(timeout_duration, async {
-                let request_url = match engine.url(encoded_query.as_ref(), options.as_ref()) {
-                    Ok(request_url) => request_url,
-                    Err(search_error) => {
-                        error!(
-                            "[{}] Error when obtaining URL: {}",
-                            engine_name, search_error
-                        );
-                        return (engine_id, Err(search_error));
-                    }
-                };
+        let Some(permit) = quaero.health.permit(engine_id) else {
+            info!("[{}] Skipping temporarily disabled engine.", engine_name);
 
-                let mut request = match Request::get(request_url).body(vec![]) {
-                    Ok(request) => request,
-                    Err(err) => {
-                        error!("[{}] Failed to build request: {:#?}", engine_name, err);
-                        return (engine_id, Err(SearchError::RequestFailed));
-                    }
-                };
+            if let Some(metrics) = &quaero.metrics {
+                metrics.engine_error(engine_id, &engine_name, &SearchError::TemporarilyDisabled);
+            }
 
-                engine.headers(request.headers_mut(), &options);
+            skipped.push(engine_id.clone());
+            continue;
+        };
 
-                let response_result = client.execute(request).await;
+        started.push(engine_id.clone());
 
-                let response = match response_result {
-                    Ok(response) => response,
-                    Err(err) => {
-                        error!("[{}] Failed to fetch results: {:#?}", engine_name, err);
-                        return (engine_id, Err(SearchError::RequestFailed));
-                    }
-                };
+        info!("[{}] Fetching search results...", engine_name);
 
-                if let Err(search_error) = engine.validate_response(&response) {
-                    error!("[{}] Failed pre-parse check: {}", engine_name, search_error);
-                    return (engine_id, Err(search_error));
-                }
+        let mut page_nums = options.page_range(engine.category_results_per_page(options.category));
 
-                if response.status() == StatusCode::TOO_MANY_REQUESTS {
-                    error!(
-                        "[{}] Failed to fetch results: {}",
-                        engine_name,
-                        SearchError::Blocked
-                    );
-                    return (engine_id, Err(SearchError::Blocked));
-                }
+        if let Some(page_cursor) = plan.page_cursors.get(engine_id) {
+            page_nums = *page_cursor..*page_cursor + page_nums.len();
+        }
 
-                let bytes = match response.bytes().await {
-                    Ok(bytes) => bytes,
-                    Err(err) => {
-                        error!(
-                            "[{}] Failed to parse response text: {:#?}",
-                            engine_name, err
-                        );
-                        return (engine_id, Err(SearchError::RequestFailed));
+        // Each engine gets the query rendered in its own syntax.
+        let encoded_query = urlencoding::encode(&engine.render_query(query)).to_string();
+
+        let fetch = EngineFetch {
+            engine_id: engine_id.clone(),
+            engine: engine.clone(),
+            engine_name,
+            clients: quaero.clients_for(engine_id),
+            encoded_query: Arc::new(encoded_query),
+            options: options.clone(),
+            middleware: quaero.middleware_for(engines, engine_id),
+            rate_limiter: quaero.rate_limiter.clone(),
+        };
+        let retry_policy = quaero.retry_policy_for(engine_id);
+        let timeout_duration = options
+            .timeout
+            .unwrap_or_else(|| engines.timeout_for(engine_id, quaero.timeout));
+        let health = quaero.health.clone();
+        let metrics = quaero.metrics.clone();
+        let rate_limiter = quaero.rate_limiter.clone();
+        let query = query.clone();
+
+        // The engine's own results are cached so they can be used while it's over its rate limit.
+        let engine_cache = match (&quaero.cache, rate_limiter.over_budget) {
+            (Some(cache), OverBudget::ServeFromCache) if rate_limiter.is_limited(engine_id) => {
+                let search_key = cache_key(&query.to_string(), &options);
+                Some((
+                    cache.clone(),
+                    rate_limit::engine_cache_key(&search_key, engine_id),
+                ))
+            }
+            _ => None,
+        };
+
+        let span = info_span!(
+            "engine",
+            engine = %fetch.engine_name,
+            status = Empty,
+            results = Empty
+        );
+
+        let task = async move {
+            let started_at = Instant::now();
+            let deadline = started_at + timeout_duration;
+
+            // Only the first page's request is taken here, as it decides whether the engine is
+            // over its rate limit. Any further pages, retries and hedges take their own.
+            if !rate_limiter.acquire(&fetch.engine_id, deadline).await {
+                let cached_results = match &engine_cache {
+                    Some((cache, key)) => {
+                        rate_limit::cached_engine_results(cache.as_ref(), key).await
                     }
+                    None => None,
                 };
 
-                let Ok(data) = str::from_utf8(&bytes) else {
-                    error!("[{}] No response text was found.", engine_name);
-                    return (engine_id, Err(SearchError::NoResponseText));
-                };
-                let data = data.to_string();
-
-                let results = tokio::task::spawn_blocking(move || engine.parse(data))
-                    .await
-                    .unwrap_or_else(|_| Err(SearchError::Unknown))
-                    .and_then(|this| {
-                        if this.len() == 0 {
-                            Err(SearchError::NoResultsFound)
-                        } else {
-                            Ok(this)
-                        }
-                    });
-
-                let results = match results {
-                    Ok(results) => results,
-                    Err(search_error) => {
-                        error!(
-                            "[{}] Failed to parse results: {}",
-                            engine_name, search_error
+                let result = match cached_results {
+                    Some(results) => {
+                        info!(
+                            "[{}] Using cached results as the engine is over its rate limit.",
+                            fetch.engine_name
+                        );
+                        let operators = fetch.engine.query_operators();
+                        Ok(enforce_operators(&query, operators, results))
+                    }
+                    None => {
+                        info!(
+                            "[{}] Skipping engine as it is over its rate limit.",
+                            fetch.engine_name
                         );
-                        return (engine_id, Err(search_error));
+                        Err(SearchError::RateLimited)
                     }
                 };
 
-                info!("[{}] Successfully fetched search results!", engine_name);
+                if let (Some(metrics), Err(search_error)) = (&metrics, &result) {
+                    metrics.engine_error(&fetch.engine_id, &fetch.engine_name, search_error);
+                }
 
-                (engine_id, Ok(results))
-            })
+                Span::current().record("status", "rate_limited");
+
+                return (fetch.engine_id, result);
+            }
+
+            // Time spent waiting for the rate limit counts towards the engine's timeout.
+            let result = tokio::time::timeout_at(
+                deadline.into(),
+                fetch.fetch_pages(page_nums, &retry_policy, &health, deadline),
+            )
             .await;
 
-            match result {
+            let result = match result {
                 Ok(result) => result,
                 Err(_err) => {
                     error!(
                         "[{}] Could not fetch results within the allowed time limit.",
-                        engine_name
+                        fetch.engine_name
                     );
-                    (outer_engine_id, Err(SearchError::Timeout))
+                    Err(SearchError::Timeout)
                 }
+            };
+
+            health.record(&fetch.engine_id, permit, &result);
+
+            if let (Some((cache, key)), Ok(results)) = (&engine_cache, &result) {
+                rate_limit::store_engine_results(cache.as_ref(), key, &fetch.engine_id, results)
+                    .await;
             }
-        });
+
+            if let Some(metrics) = &metrics {
+                metrics.engine_latency(&fetch.engine_id, &fetch.engine_name, started_at.elapsed());
+
+                if let Err(search_error) = &result {
+                    metrics.engine_error(&fetch.engine_id, &fetch.engine_name, search_error);
+                }
+            }
+
+            let operators = fetch.engine.query_operators();
+            let result = result.map(|results| enforce_operators(&query, operators, results));
+
+            let span = Span::current();
+            match &result {
+                Ok(results) => {
+                    span.record("status", "ok");
+                    span.record("results", results.len());
+                }
+                Err(search_error) => {
+                    span.record("status", search_error.kind());
+                }
+            }
+
+            (fetch.engine_id, result)
+        };
+
+        tasks.spawn(task.instrument(span));
+    }
+
+    EngineTasks {
+        tasks,
+        started,
+        skipped,
     }
+}
 
-    let mut statuses: Vec<(EngineId, Result<(), SearchError>)> = Vec::with_capacity(engines_len);
-    let mut results: HashMap<String, SearchResultWithMetadata> = HashMap::new();
+/// Drops the results which don't satisfy the operators the engine didn't understand.
+fn enforce_operators(
+    query: &SearchQuery,
+    operators: QueryOperators,
+    results: Vec<(usize, String, SearchResult)>,
+) -> Vec<(usize, String, SearchResult)> {
+    if query
+        .clauses
+        .iter()
+        .all(|clause| operators.supports(clause))
+    {
+        return results;
+    }
 
-    while let Some(Ok((engine_id, engine_results))) = tasks.join_next().await {
+    results
+        .into_iter()
+        .filter(|(_, _, result)| query.matches(result, operators))
+        .collect()
+}
+
+/// Merges the results of each engine as they come in.
+struct Aggregator {
+    query_text: String,
+    query_tokens: Vec<String>,
+    /// The query tokens which were added as synonyms, mapped to the token they're a synonym of.
+    synonym_of: HashMap<String, String>,
+    explain: bool,
+    /// Whether the score refiners should run.
+    refine: bool,
+    refine_budget: Option<Duration>,
+    ranker: Arc<dyn Ranker>,
+    rank_fusion: RankFusion,
+    near_duplicates: NearDuplicates,
+    engine_weights: HashMap<EngineId, f32>,
+    results: HashMap<String, SearchResultWithMetadata>,
+    statuses: Vec<(EngineId, Result<(), SearchError>)>,
+}
+
+impl Aggregator {
+    fn new<C: HttpClient + 'static, const N: usize>(
+        query: &SearchQuery,
+        options: &SearchOptions,
+        quaero: &Quaero<C, N>,
+        engines: &EngineSet<N>,
+    ) -> Self {
+        let engine_weights = engines
+            .engines
+            .iter()
+            .map(|TaggedEngine(engine_id, _, weight)| (engine_id.clone(), weight.get()))
+            .collect();
+
+        let text_rules = TextRules::for_language(options.language_code());
+        let query_text = query.text();
+        let (query_tokens, synonym_of) =
+            get_query_tokens(&query_text, &text_rules, options.synonyms);
+
+        Self {
+            query_tokens,
+            synonym_of,
+            explain: options.explain,
+            refine: options.refine,
+            refine_budget: options.refine_budget,
+            query_text,
+            ranker: quaero.ranker.clone(),
+            rank_fusion: quaero.rank_fusion,
+            near_duplicates: quaero.near_duplicates,
+            engine_weights,
+            results: HashMap::new(),
+            statuses: Vec::with_capacity(engines.engines.len()),
+        }
+    }
+
+    /// Merges an engine's results into the aggregated results, deduplicating by url.
+    fn merge(&mut self, engine_id: EngineId, engine_results: RankedResults) {
         let engine_results = match engine_results {
             Ok(engine_results) => engine_results,
             Err(reason) => {
-                statuses.push((engine_id, Err(reason)));
-                continue;
+                self.statuses.push((engine_id, Err(reason)));
+                return;
             }
         };
 
         let engine_results: Box<[(String, SearchResultWithMetadata)]> = engine_results
             .into_par_iter()
-            .map(|(url, result)| {
-                let mut result = SearchResultWithMetadata::new(result, &engine_id);
-                result.update_relevance(&query_tokens, &*STOP_WORDS, &*PUNCTUATION);
-
+            .map(|(rank, url, result)| {
+                let result = SearchResultWithMetadata::new(url.clone(), result, &engine_id, rank);
                 (url, result)
             })
             .collect();
 
-        for (url, mut result) in engine_results {
-            match results.entry(url) {
+        for (url, result) in engine_results {
+            match self.results.entry(url) {
                 std::collections::hash_map::Entry::Occupied(mut entry) => {
                     let existing_result = entry.get_mut();
 
-                    if existing_result.relevance_score >= result.relevance_score {
-                        existing_result.engines.extend(result.engines.into_iter());
+                    // Results aren't scored until every engine has reported,
+                    // so the one with the most text to score is kept.
+                    if snippet_len(existing_result) >= snippet_len(&result) {
+                        existing_result.merge_engines(result);
                     } else {
-                        result.engines.extend(existing_result.engines.drain(..));
-                        *existing_result = result;
+                        let previous_result = std::mem::replace(existing_result, result);
+                        existing_result.merge_engines(previous_result);
                     }
                 }
                 std::collections::hash_map::Entry::Vacant(entry) => {
@@ -196,92 +489,232 @@ pub async fn aggregate_results<C: HttpClient + 'static, const N: usize>(
             }
         }
 
-        statuses.push((engine_id, Ok(())));
+        self.statuses.push((engine_id, Ok(())));
     }
 
-    let results = sort_results(results, query, quaero.score_refiner.as_ref()).await;
+    /// Ranks a copy of the results merged so far without applying the score refiners.
+    fn snapshot(&self) -> Box<[SearchResultWithMetadata]> {
+        self.rank(self.results.values().cloned().collect())
+    }
 
-    if level_enabled!(Level::INFO) {
-        info!(
-            "Finished quaero search: {}/{} engines queried successfully.",
-            statuses.iter().filter(|this| this.1.is_ok()).count(),
-            statuses.len()
-        )
+    /// Scores the results against each other, collapses near-duplicates and sorts them.
+    fn rank(&self, mut results: Vec<SearchResultWithMetadata>) -> Box<[SearchResultWithMetadata]> {
+        let _span = info_span!("rank", results = results.len()).entered();
+
+        // Near-duplicates are collapsed into whichever has the highest lexical score,
+        // so results need scoring first.
+        let scores = self
+            .ranker
+            .scores(&self.query_text, &self.query_tokens, &results);
+
+        if scores.len() != results.len() {
+            error!(
+                "Ranker returned {} scores for {} search results.",
+                scores.len(),
+                results.len()
+            );
+        }
+
+        for (idx, result) in results.iter_mut().enumerate() {
+            let score = scores.get(idx).copied().unwrap_or_default();
+            result.lexical_score = f32::from(!score.is_nan()) * score;
+        }
+
+        if self.explain {
+            self.explain_lexical_scores(&mut results);
+        }
+
+        let mut results = rank_results(
+            collapse_near_duplicates(results, self.near_duplicates),
+            self.rank_fusion,
+            &self.engine_weights,
+        );
+
+        if self.explain {
+            explain_fusion(&mut results, self.rank_fusion, &self.engine_weights);
+        }
+
+        results
     }
 
-    SearchResponse { results, statuses }
-}
+    /// Starts an explanation for each result with its lexical score and the contribution of each token.
+    fn explain_lexical_scores(&self, results: &mut [SearchResultWithMetadata]) {
+        let token_scores = self
+            .ranker
+            .token_scores(&self.query_text, &self.query_tokens, results);
 
-async fn sort_results(
-    results: HashMap<String, SearchResultWithMetadata>,
-    query: &str,
-    score_refiner: Option<&Box<dyn ScoreRefiner + 'static>>,
-) -> Box<[SearchResultWithMetadata]> {
-    let mut results: Box<[SearchResultWithMetadata]> = results.into_values().collect();
+        for (idx, result) in results.iter_mut().enumerate() {
+            let mut tokens = token_scores
+                .as_ref()
+                .and_then(|token_scores| token_scores.get(idx).cloned())
+                .unwrap_or_default();
 
-    results.par_sort_unstable_by(|a, b| b.relevance_score.total_cmp(&a.relevance_score));
+            for token in &mut tokens {
+                token.synonym_of = self.synonym_of.get(&token.token).cloned();
+            }
 
-    if let Some(score_refiner) = score_refiner {
-        let top_results_count = score_refiner.max_results().min(results.len());
+            result.explanation = Some(Box::new(ScoreExplanation {
+                lexical_score: result.lexical_score,
+                tokens,
+                ..Default::default()
+            }));
+        }
+    }
 
-        let top_results = &mut results[0..top_results_count];
+    /// Ranks the merged results and applies the score refiners.
+    async fn finish<C: HttpClient + 'static, const N: usize>(
+        mut self,
+        query: &str,
+        quaero: &Quaero<C, N>,
+    ) -> SearchResponse {
+        let results = std::mem::take(&mut self.results);
+        let results = self.rank(results.into_values().collect());
+        let statuses = self.statuses;
+
+        let score_refiners: &[_] = match self.refine {
+            true => &quaero.score_refiners,
+            false => &[],
+        };
 
-        let top_summaries = top_results
-            .iter()
-            .map(|result| result.search_result.snippet())
-            .collect::<Box<[String]>>();
+        let mut results = refine_results(
+            results,
+            query,
+            score_refiners,
+            quaero.refiner_blend,
+            self.refine_budget.or(quaero.refine_budget),
+        )
+        .await;
 
-        let top_scores = score_refiner.scores(query, &top_summaries).await.unwrap();
+        for result in results.iter_mut() {
+            let (refined_scores, relevance_score) =
+                (&result.refined_scores, result.relevance_score);
 
-        for (idx, score) in top_scores.into_iter().enumerate() {
-            let Ok(score) = score else {
-                continue;
-            };
+            if let Some(explanation) = &mut result.explanation {
+                explanation.refined_scores = refined_scores.to_vec();
+                explanation.relevance_score = relevance_score;
+            }
+        }
 
-            top_results[idx].relevance_score = score;
+        if level_enabled!(Level::INFO) {
+            info!(
+                "Finished quaero search: {}/{} engines queried successfully.",
+                statuses.iter().filter(|this| this.1.is_ok()).count(),
+                statuses.len()
+            )
         }
 
-        top_results.par_sort_unstable_by(|a, b| b.relevance_score.total_cmp(&a.relevance_score));
+        SearchResponse {
+            results,
+            statuses,
+            cache_status: CacheStatus::Miss,
+        }
     }
+}
+
+/// Fuses the scores of each result and sorts them from most to least relevant.
+fn rank_results(
+    mut results: Box<[SearchResultWithMetadata]>,
+    rank_fusion: RankFusion,
+    engine_weights: &HashMap<EngineId, f32>,
+) -> Box<[SearchResultWithMetadata]> {
+    fuse_scores(&mut results, rank_fusion, engine_weights);
+
+    results.par_sort_unstable_by(|a, b| {
+        b.relevance_score
+            .total_cmp(&a.relevance_score)
+            .then_with(|| b.lexical_score.total_cmp(&a.lexical_score))
+    });
 
     results
 }
 
-fn get_query_tokens(query: &str) -> Vec<String> {
-    let query_tokenizer = Tokenizer::new(query, &*STOP_WORDS, Some(&*PUNCTUATION));
-    let mut query_tokens = query_tokenizer.split_into_words();
-
+/// Splits the query into tokens, returning them alongside the tokens which were added
+/// as synonyms mapped to the token they're a synonym of.
+fn get_query_tokens(
+    query: &str,
+    text_rules: &TextRules,
+    expand_synonyms: bool,
+) -> (Vec<String>, HashMap<String, String>) {
+    #[allow(unused_mut)]
+    let mut query_tokens: Vec<String> = ranking::tokenize(query)
+        .filter(|token| !text_rules.stop_words.contains(token))
+        .collect();
+
+    #[allow(unused_mut)]
+    let mut synonym_of: HashMap<String, String> = HashMap::new();
+
+    // The thesaurus only contains english synonyms.
     #[cfg(feature = "synonyms")]
-    {
-        let extra_query_tokens: Box<[String]> = query_tokens
-            .iter()
-            .flat_map(|token| thesaurus::synonyms(token))
-            .collect();
+    if expand_synonyms && text_rules.language == "en" {
+        for token in query_tokens.clone() {
+            for synonym in thesaurus::synonyms(&token) {
+                // Phrases can't match a single word of a search result.
+                if !synonym.chars().all(char::is_alphanumeric) {
+                    continue;
+                }
 
-        query_tokens.extend(extra_query_tokens);
+                let synonym = synonym.to_lowercase();
+
+                if !query_tokens.contains(&synonym) {
+                    synonym_of.insert(synonym.clone(), token.clone());
+                    query_tokens.push(synonym);
+                }
+            }
+        }
     }
 
-    query_tokens
+    #[cfg(not(feature = "synonyms"))]
+    let _ = expand_synonyms;
+
+    (query_tokens, synonym_of)
 }
 
-static STOP_WORDS: LazyLock<Box<[String]>> =
-    LazyLock::new(|| stop_words::get(stop_words::LANGUAGE::English).into_boxed_slice());
-
-static PUNCTUATION: LazyLock<[String; 14]> = LazyLock::new(|| {
-    [
-        ".".to_string(),
-        ",".to_string(),
-        ":".to_string(),
-        ";".to_string(),
-        "!".to_string(),
-        "?".to_string(),
-        "(".to_string(),
-        ")".to_string(),
-        "[".to_string(),
-        "]".to_string(),
-        "{".to_string(),
-        "}".to_string(),
-        "\"".to_string(),
-        "'".to_string(),
-    ]
-});
+/// How much text a search result has to be scored on.
+fn snippet_len(result: &SearchResultWithMetadata) -> usize {
+    result.search_result.title.len() + result.search_result.summary.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use quaero_shared::models::{ranker::Ranker, search::SearchResult};
+
+    use super::*;
+
+    fn result(title: &str, url: &str) -> SearchResultWithMetadata {
+        let (url_key, search_result) =
+            SearchResult::new(title.to_string(), url.to_string(), String::new());
+
+        SearchResultWithMetadata::new(
+            url_key,
+            search_result,
+            &"Google:V1StGXR8_Z".parse().unwrap(),
+            0,
+        )
+    }
+
+    #[test]
+    fn japanese_query_tokens() {
+        let (query_tokens, _) =
+            get_query_tokens("東京の天気", &TextRules::for_language(Some("ja")), false);
+
+        assert_eq!(query_tokens, ["東", "京", "東京", "天", "気", "天気"]);
+    }
+
+    #[test]
+    fn japanese_query_matches_japanese_title() {
+        let (query_tokens, _) =
+            get_query_tokens("東京の天気", &TextRules::for_language(Some("ja")), false);
+
+        let results = [
+            result("大阪のニュース", "https://example.com/osaka"),
+            result("東京の天気予報", "https://example.com/tokyo"),
+            result("京都の観光ガイド", "https://example.org/kyoto"),
+        ];
+
+        let scores = ranking::Bm25::default().scores("東京の天気", &query_tokens, &results);
+
+        assert_eq!(scores[0], 0.);
+        assert!(scores[1] > scores[2]);
+        assert!(scores[2] > 0.);
+    }
+}
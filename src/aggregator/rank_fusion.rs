@@ -0,0 +1,283 @@
+use quaero_shared::models::{
+    engine::EngineId,
+    search::{EngineContribution, RankFusion, ScoreAdjustment, SearchResultWithMetadata},
+};
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+/// Combines the rank each engine gave a search result with its lexical score,
+/// taking into account how much each engine is trusted and how many of them agree.
+pub fn fuse_scores(
+    results: &mut [SearchResultWithMetadata],
+    fusion: RankFusion,
+    engine_weights: &HashMap<EngineId, f32>,
+) {
+    match fusion {
+        RankFusion::Lexical => results.par_iter_mut().for_each(|result| {
+            result.relevance_score = result.lexical_score * consensus(result, engine_weights)
+        }),
+
+        RankFusion::ReciprocalRank { k } => results.par_iter_mut().for_each(|result| {
+            result.relevance_score = reciprocal_rank_score(result, k, engine_weights)
+        }),
+
+        RankFusion::Weighted { k, rank_weight } => {
+            let rank_scores: Box<[f32]> = results
+                .par_iter()
+                .map(|result| reciprocal_rank_score(result, k, engine_weights))
+                .collect();
+
+            let max_rank_score = rank_scores.iter().copied().fold(0., f32::max);
+            let max_lexical_score = results
+                .iter()
+                .map(|result| result.lexical_score)
+                .fold(0., f32::max);
+
+            results
+                .par_iter_mut()
+                .zip(rank_scores.par_iter())
+                .for_each(|(result, rank_score)| {
+                    let rank_score = normalize(*rank_score, max_rank_score);
+                    let lexical_score = normalize(result.lexical_score, max_lexical_score)
+                        * mean_weight(result, engine_weights);
+
+                    result.relevance_score =
+                        rank_weight * rank_score + (1. - rank_weight) * lexical_score;
+                });
+        }
+    }
+}
+
+/// Fills in how each engine contributed to the fused score of each explained search result.
+pub fn explain_fusion(
+    results: &mut [SearchResultWithMetadata],
+    fusion: RankFusion,
+    engine_weights: &HashMap<EngineId, f32>,
+) {
+    let k = match fusion {
+        RankFusion::Lexical => None,
+        RankFusion::ReciprocalRank { k } | RankFusion::Weighted { k, .. } => Some(k),
+    };
+
+    results.par_iter_mut().for_each(|result| {
+        let engines = result
+            .ranks
+            .iter()
+            .map(|(engine_id, rank)| {
+                let weight = engine_weight(engine_id, engine_weights);
+
+                EngineContribution {
+                    engine_id: engine_id.clone(),
+                    rank: *rank,
+                    weight,
+                    rank_score: k.map(|k| weight / (k + *rank as f32 + 1.)),
+                }
+            })
+            .collect();
+
+        let adjustments = match fusion {
+            RankFusion::Lexical => vec![ScoreAdjustment {
+                reason: "consensus".to_string(),
+                factor: consensus(result, engine_weights),
+            }],
+            RankFusion::ReciprocalRank { .. } => vec![],
+            RankFusion::Weighted { .. } => vec![ScoreAdjustment {
+                reason: "engine_weight".to_string(),
+                factor: mean_weight(result, engine_weights),
+            }],
+        };
+
+        let relevance_score = result.relevance_score;
+
+        if let Some(explanation) = &mut result.explanation {
+            explanation.engines = engines;
+            explanation.adjustments = adjustments;
+            explanation.fused_score = relevance_score;
+            explanation.relevance_score = relevance_score;
+        }
+    });
+}
+
+/// Sums `weight / (k + rank)` for each engine which returned the search result.
+fn reciprocal_rank_score(
+    result: &SearchResultWithMetadata,
+    k: f32,
+    engine_weights: &HashMap<EngineId, f32>,
+) -> f32 {
+    result
+        .ranks
+        .iter()
+        // Ranks are stored starting at 0 but RRF expects them to start at 1.
+        .map(|(engine_id, rank)| engine_weight(engine_id, engine_weights) / (k + *rank as f32 + 1.))
+        .sum()
+}
+
+/// The mean weight of the engines which returned the search result,
+/// boosted logarithmically by how many of them agree on it.
+fn consensus(result: &SearchResultWithMetadata, engine_weights: &HashMap<EngineId, f32>) -> f32 {
+    let agreeing_engines = result.ranks.len().max(1) as f32;
+
+    mean_weight(result, engine_weights) * (1. + agreeing_engines.ln())
+}
+
+fn mean_weight(result: &SearchResultWithMetadata, engine_weights: &HashMap<EngineId, f32>) -> f32 {
+    if result.ranks.is_empty() {
+        return 1.;
+    }
+
+    let total_weight: f32 = result
+        .ranks
+        .iter()
+        .map(|(engine_id, _)| engine_weight(engine_id, engine_weights))
+        .sum();
+
+    total_weight / result.ranks.len() as f32
+}
+
+#[inline(always)]
+fn engine_weight(engine_id: &EngineId, engine_weights: &HashMap<EngineId, f32>) -> f32 {
+    engine_weights.get(engine_id).copied().unwrap_or(1.)
+}
+
+#[inline(always)]
+fn normalize(score: f32, max_score: f32) -> f32 {
+    if max_score > 0. {
+        score / max_score
+    } else {
+        0.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quaero_shared::models::search::SearchResult;
+    use smallvec::SmallVec;
+
+    use super::*;
+
+    fn engine_id(name: &str) -> EngineId {
+        format!("{name}:V1StGXR8_Z").parse().unwrap()
+    }
+
+    fn result(ranks: &[(&str, usize)], lexical_score: f32) -> SearchResultWithMetadata {
+        let (url_key, search_result) = SearchResult::new(
+            String::new(),
+            format!("https://example.com/{}", ranks[0].1),
+            String::new(),
+        );
+
+        let mut result =
+            SearchResultWithMetadata::new(url_key, search_result, &engine_id(ranks[0].0), 0);
+        result.engines = ranks.iter().map(|(name, _)| engine_id(name)).collect();
+        result.ranks = ranks
+            .iter()
+            .map(|(name, rank)| (engine_id(name), *rank))
+            .collect::<SmallVec<_>>();
+        result.lexical_score = lexical_score;
+        result
+    }
+
+    /// A search result in the top three on Google, Bing and Brave,
+    /// and one only Mojeek returned, ninth.
+    fn consensus_and_mojeek_only(
+        consensus_lexical_score: f32,
+        mojeek_lexical_score: f32,
+    ) -> [SearchResultWithMetadata; 2] {
+        [
+            result(
+                &[("Google", 0), ("Bing", 1), ("Brave", 2)],
+                consensus_lexical_score,
+            ),
+            result(&[("Mojeek", 8)], mojeek_lexical_score),
+        ]
+    }
+
+    fn fused(
+        mut results: [SearchResultWithMetadata; 2],
+        fusion: RankFusion,
+        engine_weights: &HashMap<EngineId, f32>,
+    ) -> [f32; 2] {
+        fuse_scores(&mut results, fusion, engine_weights);
+        results.map(|result| result.relevance_score)
+    }
+
+    #[test]
+    fn reciprocal_rank_sums_each_engines_contribution() {
+        let [consensus, mojeek] = fused(
+            consensus_and_mojeek_only(0., 0.),
+            RankFusion::reciprocal_rank(),
+            &HashMap::new(),
+        );
+
+        assert_eq!(consensus, 1. / 61. + 1. / 62. + 1. / 63.);
+        assert_eq!(mojeek, 1. / 69.);
+    }
+
+    #[test]
+    fn reciprocal_rank_scales_by_engine_weight() {
+        let engine_weights = HashMap::from([(engine_id("Mojeek"), 2.)]);
+        let [_, mojeek] = fused(
+            consensus_and_mojeek_only(0., 0.),
+            RankFusion::reciprocal_rank(),
+            &engine_weights,
+        );
+
+        assert_eq!(mojeek, 2. / 69.);
+    }
+
+    #[test]
+    fn reciprocal_rank_prefers_consensus() {
+        // The lexical score isn't taken into account, however lopsided it is.
+        let [consensus, mojeek] = fused(
+            consensus_and_mojeek_only(0., 100.),
+            RankFusion::reciprocal_rank(),
+            &HashMap::new(),
+        );
+
+        assert!(consensus > mojeek);
+    }
+
+    #[test]
+    fn weighted_blends_normalized_scores() {
+        let [consensus, mojeek] = fused(
+            consensus_and_mojeek_only(5., 10.),
+            RankFusion::weighted(0.5),
+            &HashMap::new(),
+        );
+
+        let mojeek_rank_score = (1. / 69.) / (1. / 61. + 1. / 62. + 1. / 63.);
+
+        assert_eq!(consensus, 0.5 * 1. + 0.5 * 0.5);
+        assert_eq!(mojeek, 0.5 * mojeek_rank_score + 0.5 * 1.);
+    }
+
+    #[test]
+    fn weighted_prefers_consensus() {
+        for rank_weight in [0.5, 0.7, 1.] {
+            let fusion = RankFusion::weighted(rank_weight);
+
+            for (consensus_lexical_score, mojeek_lexical_score) in [(1., 1.), (4., 10.)] {
+                let [consensus, mojeek] = fused(
+                    consensus_and_mojeek_only(consensus_lexical_score, mojeek_lexical_score),
+                    fusion,
+                    &HashMap::new(),
+                );
+
+                assert!(consensus > mojeek, "{fusion:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn default_weighting_can_prefer_much_more_relevant_results() {
+        // The consensus result barely matches the query, so its lexical score
+        // outweighs the gap between the rank scores.
+        let results = consensus_and_mojeek_only(1., 10.);
+
+        let [consensus, mojeek] = fused(results.clone(), RankFusion::default(), &HashMap::new());
+        assert!(consensus < mojeek);
+
+        let [consensus, mojeek] = fused(results, RankFusion::weighted(0.6), &HashMap::new());
+        assert!(consensus > mojeek);
+    }
+}
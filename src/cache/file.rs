@@ -0,0 +1,325 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::Mutex;
+use tracing::error;
+
+use quaero_shared::models::{
+    search::{CacheStatus, SearchResponse},
+    search_cache::{CacheExpiry, SearchCache},
+};
+
+/// A search cache which stores each response as a JSON file inside a directory.
+/// The least recently used responses are removed once it is full.
+///
+/// A response's file is touched each time it is served, so its modification time is
+/// when it was last used. Once the cache is full, the least recently used tenth of the
+/// responses are removed together, so the directory isn't scanned on every insert.
+pub struct FileCache {
+    directory: PathBuf,
+    expiry: CacheExpiry,
+    max_entries: usize,
+    // How many responses are stored, which is counted from the directory on first use.
+    entry_count: Mutex<Option<usize>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheFile<R> {
+    key: String,
+    created_at: u64,
+    response: R,
+}
+
+impl FileCache {
+    /// Creates a new file-backed cache which holds up to `max_entries` responses for `ttl`.
+    /// The directory is created if it doesn't already exist.
+    pub fn new(directory: impl Into<PathBuf>, max_entries: usize, ttl: Duration) -> Self {
+        Self {
+            directory: directory.into(),
+            expiry: CacheExpiry::new(ttl),
+            max_entries: max_entries.max(1),
+            entry_count: Mutex::new(None),
+        }
+    }
+
+    /// Keeps serving responses for `window` after their ttl has passed
+    /// while fresh responses are fetched in the background.
+    pub fn stale_while_revalidate(mut self, window: Duration) -> Self {
+        self.expiry.stale_while_revalidate = Some(window);
+        self
+    }
+
+    fn path_for(&self, key: &str) -> PathBuf {
+        self.directory.join(format!("{:016x}.json", fnv1a(key)))
+    }
+
+    /// Returns the modification time and path of each cached response.
+    async fn files(&self) -> std::io::Result<Vec<(SystemTime, PathBuf)>> {
+        let mut entries = tokio::fs::read_dir(&self.directory).await?;
+        let mut files = Vec::new();
+
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                let modified = entry.metadata().await?.modified()?;
+                files.push((modified, path));
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// Removes the least recently used files, returning how many are left.
+    async fn evict(&self) -> std::io::Result<usize> {
+        let mut files = self.files().await?;
+
+        if files.len() <= self.max_entries {
+            return Ok(files.len());
+        }
+
+        files.sort_unstable();
+
+        let keep = self.max_entries - self.max_entries / 10;
+        for (_, path) in &files[..files.len() - keep] {
+            let _ = tokio::fs::remove_file(path).await;
+        }
+
+        Ok(keep)
+    }
+
+    /// Removes a cached response which can no longer be served.
+    async fn remove(&self, path: &Path) {
+        if tokio::fs::remove_file(path).await.is_ok() {
+            let mut entry_count = self.entry_count.lock().await;
+            if let Some(entry_count) = entry_count.as_mut() {
+                *entry_count = entry_count.saturating_sub(1);
+            }
+        }
+    }
+}
+
+/// Marks a cached response as just used by updating its modification time.
+async fn touch(path: &Path) -> std::io::Result<()> {
+    let file = tokio::fs::OpenOptions::new().write(true).open(path).await?;
+    file.into_std().await.set_modified(SystemTime::now())
+}
+
+#[async_trait::async_trait]
+impl SearchCache for FileCache {
+    async fn get(&self, key: &str) -> Option<(SearchResponse, CacheStatus)> {
+        let path = self.path_for(key);
+
+        let bytes = tokio::fs::read(&path).await.ok()?;
+        let Ok(file) = serde_json::from_slice::<CacheFile<SearchResponse>>(&bytes) else {
+            self.remove(&path).await;
+            return None;
+        };
+
+        // Guards against hash collisions between keys.
+        if file.key != key {
+            return None;
+        }
+
+        let created_at = UNIX_EPOCH + Duration::from_secs(file.created_at);
+
+        let Some(status) = self.expiry.status(created_at) else {
+            self.remove(&path).await;
+            return None;
+        };
+
+        if let Err(err) = touch(&path).await {
+            error!("Failed to touch cached response: {:#?}", err);
+        }
+
+        Some((file.response, status))
+    }
+
+    async fn insert(&self, key: &str, response: &SearchResponse) {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let file = CacheFile {
+            key: key.to_string(),
+            created_at,
+            response,
+        };
+
+        let bytes = match serde_json::to_vec(&file) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                error!("Failed to serialize cached response: {:#?}", err);
+                return;
+            }
+        };
+
+        if let Err(err) = tokio::fs::create_dir_all(&self.directory).await {
+            error!("Failed to create cache directory: {:#?}", err);
+            return;
+        }
+
+        // Inserts are serialized so the count stays in sync with the directory.
+        let mut entry_count = self.entry_count.lock().await;
+
+        let count = match *entry_count {
+            Some(count) => count,
+            None => match self.files().await {
+                Ok(files) => files.len(),
+                Err(err) => {
+                    error!("Failed to read cache directory: {:#?}", err);
+                    return;
+                }
+            },
+        };
+
+        let path = self.path_for(key);
+        let is_new = !tokio::fs::try_exists(&path).await.unwrap_or(false);
+
+        if let Err(err) = tokio::fs::write(&path, bytes).await {
+            error!("Failed to write cached response: {:#?}", err);
+            *entry_count = Some(count);
+            return;
+        }
+
+        let count = count + is_new as usize;
+
+        *entry_count = match count > self.max_entries {
+            true => match self.evict().await {
+                Ok(count) => Some(count),
+                Err(err) => {
+                    error!("Failed to evict cached responses: {:#?}", err);
+                    // The directory is counted again next time.
+                    None
+                }
+            },
+            false => Some(count),
+        };
+    }
+}
+
+/// A stable hash used for naming cache files.
+fn fnv1a(key: &str) -> u64 {
+    key.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response() -> SearchResponse {
+        SearchResponse {
+            results: Box::default(),
+            statuses: Vec::new(),
+            cache_status: CacheStatus::Miss,
+        }
+    }
+
+    /// A temporary cache directory, which is removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let directory = std::env::temp_dir().join(format!(
+                "quaero-file-cache-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = std::fs::remove_dir_all(&directory);
+
+            Self(directory)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    async fn status(cache: &FileCache, key: &str) -> Option<CacheStatus> {
+        cache.get(key).await.map(|(_, status)| status)
+    }
+
+    #[tokio::test]
+    async fn evicts_least_recently_used() {
+        let directory = TempDir::new("lru");
+        let cache = &FileCache::new(&directory.0, 10, Duration::from_secs(60));
+
+        for idx in 0..10 {
+            cache.insert(&format!("key {idx}"), &response()).await;
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        // Serving a response makes it the most recently used.
+        assert_eq!(status(cache, "key 0").await, Some(CacheStatus::Hit));
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        // Replacing a response doesn't count as a new entry.
+        cache.insert("key 9", &response()).await;
+        assert_eq!(*cache.entry_count.lock().await, Some(10));
+
+        // Going over the limit frees up a tenth of the space.
+        cache.insert("key 10", &response()).await;
+        assert_eq!(*cache.entry_count.lock().await, Some(9));
+
+        assert_eq!(status(cache, "key 0").await, Some(CacheStatus::Hit));
+        assert_eq!(status(cache, "key 1").await, None);
+        assert_eq!(status(cache, "key 2").await, None);
+        for idx in 3..=10 {
+            assert_eq!(
+                status(cache, &format!("key {idx}")).await,
+                Some(CacheStatus::Hit)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn counts_existing_files() {
+        let directory = TempDir::new("existing");
+        let cache = FileCache::new(&directory.0, 3, Duration::from_secs(60));
+
+        for key in ["a", "b", "c"] {
+            cache.insert(key, &response()).await;
+        }
+
+        // A new cache over the same directory counts the responses which are already there.
+        let reopened = FileCache::new(&directory.0, 3, Duration::from_secs(60));
+        reopened.insert("d", &response()).await;
+
+        assert_eq!(*reopened.entry_count.lock().await, Some(3));
+        assert_eq!(reopened.files().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn removes_expired_responses() {
+        let directory = TempDir::new("expiry");
+        let cache = &FileCache::new(&directory.0, 10, Duration::ZERO);
+
+        cache.insert("key", &response()).await;
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        assert_eq!(status(cache, "key").await, None);
+        assert_eq!(*cache.entry_count.lock().await, Some(0));
+        assert!(cache.files().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn serves_stale_responses_while_revalidating() {
+        let directory = TempDir::new("stale");
+        let cache = FileCache::new(&directory.0, 10, Duration::ZERO)
+            .stale_while_revalidate(Duration::from_secs(60));
+
+        cache.insert("key", &response()).await;
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        assert_eq!(status(&cache, "key").await, Some(CacheStatus::Stale));
+    }
+}
@@ -0,0 +1,181 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+use quaero_shared::models::{
+    search::{CacheStatus, SearchResponse},
+    search_cache::{CacheExpiry, SearchCache},
+};
+
+/// An in-memory search cache which evicts the least recently used responses once it is full.
+pub struct MemoryCache {
+    expiry: CacheExpiry,
+    max_entries: usize,
+    state: Mutex<LruState>,
+}
+
+#[derive(Default)]
+struct LruState {
+    entries: HashMap<String, LruEntry>,
+    // Maps when an entry was last used to its key, so the oldest one is always first.
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+struct LruEntry {
+    response: SearchResponse,
+    created_at: SystemTime,
+    last_used: u64,
+}
+
+impl MemoryCache {
+    /// Creates a new in-memory cache which holds up to `max_entries` responses for `ttl`.
+    pub fn new(max_entries: usize, ttl: Duration) -> Self {
+        Self {
+            expiry: CacheExpiry::new(ttl),
+            max_entries: max_entries.max(1),
+            state: Mutex::new(LruState::default()),
+        }
+    }
+
+    /// Keeps serving responses for `window` after their ttl has passed
+    /// while fresh responses are fetched in the background.
+    pub fn stale_while_revalidate(mut self, window: Duration) -> Self {
+        self.expiry.stale_while_revalidate = Some(window);
+        self
+    }
+}
+
+impl LruState {
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn remove(&mut self, key: &str) -> Option<LruEntry> {
+        let entry = self.entries.remove(key)?;
+        self.recency.remove(&entry.last_used);
+        Some(entry)
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchCache for MemoryCache {
+    async fn get(&self, key: &str) -> Option<(SearchResponse, CacheStatus)> {
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+
+        let created_at = state.entries.get(key)?.created_at;
+
+        let Some(status) = self.expiry.status(created_at) else {
+            state.remove(key);
+            return None;
+        };
+
+        let tick = state.next_tick();
+        let entry = state.entries.get_mut(key)?;
+        let previously_used = std::mem::replace(&mut entry.last_used, tick);
+        let response = entry.response.clone();
+
+        state.recency.remove(&previously_used);
+        state.recency.insert(tick, key.to_string());
+
+        Some((response, status))
+    }
+
+    async fn insert(&self, key: &str, response: &SearchResponse) {
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+
+        state.remove(key);
+
+        let tick = state.next_tick();
+        state.entries.insert(
+            key.to_string(),
+            LruEntry {
+                response: response.clone(),
+                created_at: SystemTime::now(),
+                last_used: tick,
+            },
+        );
+        state.recency.insert(tick, key.to_string());
+
+        while state.entries.len() > self.max_entries {
+            let Some((_, oldest_key)) = state.recency.pop_first() else {
+                break;
+            };
+            state.entries.remove(&oldest_key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response() -> SearchResponse {
+        SearchResponse {
+            results: Box::default(),
+            statuses: Vec::new(),
+            cache_status: CacheStatus::Miss,
+        }
+    }
+
+    async fn status(cache: &MemoryCache, key: &str) -> Option<CacheStatus> {
+        cache.get(key).await.map(|(_, status)| status)
+    }
+
+    #[tokio::test]
+    async fn evicts_least_recently_used() {
+        let cache = MemoryCache::new(2, Duration::from_secs(60));
+
+        cache.insert("a", &response()).await;
+        cache.insert("b", &response()).await;
+
+        // Serving a response makes it the most recently used.
+        assert_eq!(status(&cache, "a").await, Some(CacheStatus::Hit));
+
+        cache.insert("c", &response()).await;
+        assert_eq!(status(&cache, "b").await, None);
+        assert_eq!(status(&cache, "a").await, Some(CacheStatus::Hit));
+        assert_eq!(status(&cache, "c").await, Some(CacheStatus::Hit));
+
+        // Replacing a response doesn't evict anything.
+        cache.insert("a", &response()).await;
+        assert_eq!(status(&cache, "c").await, Some(CacheStatus::Hit));
+        assert_eq!(cache.state.lock().unwrap().entries.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn removes_expired_responses() {
+        let cache = MemoryCache::new(10, Duration::from_millis(10));
+
+        cache.insert("key", &response()).await;
+        assert_eq!(status(&cache, "key").await, Some(CacheStatus::Hit));
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(status(&cache, "key").await, None);
+
+        let state = cache.state.lock().unwrap();
+        assert!(state.entries.is_empty());
+        assert!(state.recency.is_empty());
+    }
+
+    #[tokio::test]
+    async fn serves_stale_responses_while_revalidating() {
+        let cache = MemoryCache::new(10, Duration::from_millis(10))
+            .stale_while_revalidate(Duration::from_millis(40));
+
+        cache.insert("key", &response()).await;
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(status(&cache, "key").await, Some(CacheStatus::Stale));
+
+        // A fresh response replaces the stale one.
+        cache.insert("key", &response()).await;
+        assert_eq!(status(&cache, "key").await, Some(CacheStatus::Hit));
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(status(&cache, "key").await, None);
+    }
+}
@@ -0,0 +1,20 @@
+//! Search cache implementations.
+
+mod memory;
+pub use memory::*;
+
+#[cfg(feature = "file-cache")]
+mod file;
+#[cfg(feature = "file-cache")]
+pub use file::*;
+
+pub use quaero_shared::models::search_cache::*;
+
+use quaero_shared::models::search::SearchResponse;
+
+/// Stores a response in the cache as long as at least one engine succeeded.
+pub(crate) async fn store(cache: &dyn SearchCache, key: &str, response: &SearchResponse) {
+    if response.statuses.iter().any(|(_, status)| status.is_ok()) {
+        cache.insert(key, response).await;
+    }
+}
@@ -0,0 +1,73 @@
+//! Pools of http clients which engines send their requests through.
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use anyhttp::HttpClient;
+
+/// A pool of http clients (such as clients for different proxies) which are used one at a time.
+/// Whenever an engine is blocked or shown a captcha through the current client,
+/// the pool rotates to the next one.
+pub struct ClientPool<C: HttpClient + 'static> {
+    clients: Box<[Arc<C>]>,
+    current: AtomicUsize,
+}
+
+impl<C: HttpClient + 'static> ClientPool<C> {
+    /// Creates a new pool from the specified clients.
+    ///
+    /// # Panics
+    /// If no clients are specified.
+    pub fn new(clients: impl IntoIterator<Item = impl Into<Arc<C>>>) -> Self {
+        let clients: Box<[Arc<C>]> = clients.into_iter().map(Into::into).collect();
+        assert!(
+            !clients.is_empty(),
+            "a client pool needs at least one client"
+        );
+
+        Self {
+            clients,
+            current: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns how many clients are in the pool.
+    pub fn len(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Returns whether the pool is empty, which is never the case.
+    pub fn is_empty(&self) -> bool {
+        self.clients.is_empty()
+    }
+
+    /// Returns the index of the client currently in use, along with the client itself.
+    pub fn current(&self) -> (usize, Arc<C>) {
+        let idx = self.current.load(Ordering::Relaxed) % self.clients.len();
+        (idx, self.clients[idx].clone())
+    }
+
+    /// Rotates to the next client, as long as the specified client is still the current one.
+    /// This stops concurrent failures through the same client from skipping over other clients.
+    ///
+    /// Returns whether the pool was rotated.
+    pub fn rotate_from(&self, idx: usize) -> bool {
+        if self.clients.len() <= 1 {
+            return false;
+        }
+
+        let next_idx = (idx + 1) % self.clients.len();
+
+        self.current
+            .compare_exchange(idx, next_idx, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+    }
+}
+
+impl<C: HttpClient + 'static> From<Arc<C>> for ClientPool<C> {
+    fn from(client: Arc<C>) -> Self {
+        Self::new([client])
+    }
+}
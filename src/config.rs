@@ -0,0 +1,826 @@
+//! Declarative configuration for building a quaero instance, loaded from TOML or JSON
+//! with environment-variable overrides.
+//!
+//! ## Example
+//! ```toml
+//! timeout_ms = 10000
+//!
+//! [cache]
+//! max_entries = 1000
+//! ttl_secs = 300
+//!
+//! [refiner]
+//! normalization = "min_max"
+//! refined_weight = 0.7
+//! budget_ms = 500
+//!
+//! [[engines]]
+//! name = "google"
+//! weight = 1.5
+//! timeout_ms = 5000
+//! headers = { "Accept-Language" = "en-GB" }
+//!
+//! [[engines]]
+//! name = "yandex"
+//! enabled = false
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhttp::HttpClient;
+use http::{HeaderMap, HeaderName, HeaderValue, Request, Uri};
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+use thiserror::Error;
+
+use quaero_shared::models::{
+    engine::{EngineId, EngineRegistry, TaggedEngine},
+    search::SearchError,
+};
+
+use crate::{
+    Quaero,
+    cache::MemoryCache,
+    engine_set::EngineSet,
+    middleware::Middleware,
+    refine::{RefinerBlend, ScoreNormalization},
+};
+
+/// The prefix of every environment variable which overrides the configuration.
+const ENV_PREFIX: &str = "QUAERO_";
+
+/// The configuration of a quaero instance.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuaeroConfig {
+    /// The maximum amount of time (in milliseconds) each engine has to fetch its results.
+    /// Uses the default timeout if not set.
+    pub timeout_ms: Option<u64>,
+
+    /// The engines to query, in order.
+    pub engines: Vec<EngineConfig>,
+
+    /// The search cache, or `None` for no cache.
+    pub cache: Option<CacheConfig>,
+
+    /// How the score refiners' scores are blended. The refiners themselves
+    /// need to be initialized in code, see [Quaero::score_refiner].
+    pub refiner: RefinerConfig,
+}
+
+/// The configuration of an engine.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EngineConfig {
+    /// The name the engine is registered under in the [EngineRegistry].
+    pub name: String,
+
+    /// Whether the engine is queried.
+    pub enabled: bool,
+
+    /// How much the engine's results are trusted relative to other engines.
+    pub weight: Option<f32>,
+
+    /// The maximum amount of time (in milliseconds) the engine has to fetch its results,
+    /// overriding the global timeout.
+    pub timeout_ms: Option<u64>,
+
+    /// Replaces the scheme and host of every request made to the engine,
+    /// such as to use a regional domain or a mirror. Its path (if any) is
+    /// prepended to the engine's own, while query strings aren't allowed.
+    pub base_url: Option<String>,
+
+    /// Headers added to every request made to the engine,
+    /// replacing any headers of the same name.
+    pub headers: BTreeMap<String, String>,
+}
+
+impl EngineConfig {
+    /// Creates the configuration for an enabled engine.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            enabled: true,
+            weight: None,
+            timeout_ms: None,
+            base_url: None,
+            headers: BTreeMap::new(),
+        }
+    }
+}
+
+/// The configuration of the in-memory search cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// The maximum amount of responses stored at once.
+    pub max_entries: usize,
+
+    /// How long (in seconds) responses are fresh for.
+    pub ttl_secs: u64,
+
+    /// How long (in seconds) after expiring responses are served while being refreshed.
+    pub stale_while_revalidate_secs: Option<u64>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 1000,
+            ttl_secs: 300,
+            stale_while_revalidate_secs: None,
+        }
+    }
+}
+
+/// The configuration of the score refiner pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RefinerConfig {
+    /// How scores are normalised before being blended,
+    /// either `min_max` or `z_score`.
+    pub normalization: NormalizationConfig,
+
+    /// How much the refined score contributes to the final score (between `0.0` and `1.0`).
+    pub refined_weight: f32,
+
+    /// How long (in milliseconds) the score refiners have to refine the results of a search, in total.
+    pub budget_ms: Option<u64>,
+}
+
+impl Default for RefinerConfig {
+    fn default() -> Self {
+        let blend = RefinerBlend::default();
+
+        Self {
+            normalization: blend.normalization.into(),
+            refined_weight: blend.refined_weight,
+            budget_ms: None,
+        }
+    }
+}
+
+/// The name of a [ScoreNormalization] in the configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NormalizationConfig {
+    /// See [ScoreNormalization::MinMax].
+    #[default]
+    MinMax,
+
+    /// See [ScoreNormalization::ZScore].
+    ZScore,
+}
+
+impl From<ScoreNormalization> for NormalizationConfig {
+    fn from(normalization: ScoreNormalization) -> Self {
+        match normalization {
+            ScoreNormalization::MinMax => Self::MinMax,
+            ScoreNormalization::ZScore => Self::ZScore,
+        }
+    }
+}
+
+impl From<NormalizationConfig> for ScoreNormalization {
+    fn from(normalization: NormalizationConfig) -> Self {
+        match normalization {
+            NormalizationConfig::MinMax => Self::MinMax,
+            NormalizationConfig::ZScore => Self::ZScore,
+        }
+    }
+}
+
+/// Potential errors when loading a configuration or building a quaero instance from it.
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    /// The configuration file couldn't be read.
+    #[error("Could not read the configuration file: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The configuration file's extension isn't `toml` or `json`.
+    #[error("The configuration file {0:?} needs a `toml` or `json` extension.")]
+    UnknownFormat(PathBuf),
+
+    /// The TOML configuration is invalid.
+    #[error("The configuration is invalid: {0}")]
+    Toml(#[from] toml::de::Error),
+
+    /// The JSON configuration is invalid.
+    #[error("The configuration is invalid: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// An environment variable override has a value which couldn't be parsed.
+    #[error("The environment variable {name} has an invalid value {value:?}.")]
+    InvalidEnvVar {
+        /// The name of the environment variable.
+        name: String,
+        /// Its value.
+        value: String,
+    },
+
+    /// No engine is registered under the specified name.
+    #[error("No engine is registered under the name {0:?}.")]
+    UnknownEngine(String),
+
+    /// An engine's base url isn't an absolute url, or has a query string.
+    #[error("The base url {url:?} of the {engine} engine is invalid.")]
+    InvalidBaseUrl {
+        /// The name of the engine.
+        engine: String,
+        /// The base url.
+        url: String,
+    },
+
+    /// An engine's header has an invalid name or value.
+    #[error("The header {header:?} of the {engine} engine is invalid.")]
+    InvalidHeader {
+        /// The name of the engine.
+        engine: String,
+        /// The name of the header.
+        header: String,
+    },
+}
+
+impl QuaeroConfig {
+    /// Parses a configuration from TOML.
+    pub fn from_toml_str(toml: &str) -> Result<Self, ConfigError> {
+        Ok(toml::from_str(toml)?)
+    }
+
+    /// Parses a configuration from JSON.
+    pub fn from_json_str(json: &str) -> Result<Self, ConfigError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Reads a configuration from a `.toml` or `.json` file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+
+        let parse = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Self::from_toml_str,
+            Some("json") => Self::from_json_str,
+            _ => return Err(ConfigError::UnknownFormat(path.to_path_buf())),
+        };
+
+        parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Overrides the configuration with the process's `QUAERO_` environment variables.
+    /// See [QuaeroConfig::apply_env].
+    pub fn apply_env_overrides(self) -> Result<Self, ConfigError> {
+        self.apply_env(std::env::vars())
+    }
+
+    /// Overrides the configuration with any of the following variables:
+    ///
+    /// - `QUAERO_TIMEOUT_MS`
+    /// - `QUAERO_CACHE_MAX_ENTRIES`, `QUAERO_CACHE_TTL_SECS` and
+    ///   `QUAERO_CACHE_STALE_WHILE_REVALIDATE_SECS` (these enable the cache)
+    /// - `QUAERO_REFINER_NORMALIZATION`, `QUAERO_REFINER_WEIGHT` and `QUAERO_REFINER_BUDGET_MS`
+    /// - `QUAERO_ENGINE_<NAME>_ENABLED`, `QUAERO_ENGINE_<NAME>_WEIGHT`,
+    ///   `QUAERO_ENGINE_<NAME>_TIMEOUT_MS` and `QUAERO_ENGINE_<NAME>_BASE_URL`
+    ///
+    /// `<NAME>` is the engine's name in uppercase, with anything other than letters
+    /// and digits replaced by underscores. Engines which aren't in the configuration
+    /// are appended to it, so `QUAERO_ENGINE_YANDEX_ENABLED=false` disables Yandex
+    /// and `QUAERO_ENGINE_MOJEEK_ENABLED=true` adds Mojeek.
+    /// Any other variables are ignored.
+    pub fn apply_env(
+        mut self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, ConfigError> {
+        for (name, value) in vars {
+            let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+
+            let invalid = || ConfigError::InvalidEnvVar {
+                name: name.clone(),
+                value: value.clone(),
+            };
+
+            match key {
+                "TIMEOUT_MS" => self.timeout_ms = Some(parse_env(&value).ok_or_else(invalid)?),
+                "CACHE_MAX_ENTRIES" => {
+                    self.cache.get_or_insert_default().max_entries =
+                        parse_env(&value).ok_or_else(invalid)?
+                }
+                "CACHE_TTL_SECS" => {
+                    self.cache.get_or_insert_default().ttl_secs =
+                        parse_env(&value).ok_or_else(invalid)?
+                }
+                "CACHE_STALE_WHILE_REVALIDATE_SECS" => {
+                    self.cache
+                        .get_or_insert_default()
+                        .stale_while_revalidate_secs = Some(parse_env(&value).ok_or_else(invalid)?)
+                }
+                "REFINER_NORMALIZATION" => {
+                    self.refiner.normalization = match value.trim().to_lowercase().as_str() {
+                        "min_max" => NormalizationConfig::MinMax,
+                        "z_score" => NormalizationConfig::ZScore,
+                        _ => return Err(invalid()),
+                    }
+                }
+                "REFINER_WEIGHT" => {
+                    self.refiner.refined_weight = parse_env(&value).ok_or_else(invalid)?
+                }
+                "REFINER_BUDGET_MS" => {
+                    self.refiner.budget_ms = Some(parse_env(&value).ok_or_else(invalid)?)
+                }
+                _ => {
+                    if let Some(key) = key.strip_prefix("ENGINE_") {
+                        self.apply_engine_env(key, &value).ok_or_else(invalid)?;
+                    }
+                }
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Applies an engine override, returning `None` if its value couldn't be parsed.
+    fn apply_engine_env(&mut self, key: &str, value: &str) -> Option<()> {
+        const FIELDS: [&str; 4] = ["_ENABLED", "_WEIGHT", "_TIMEOUT_MS", "_BASE_URL"];
+
+        let Some((env_name, field)) = FIELDS
+            .iter()
+            .find_map(|field| Some((key.strip_suffix(field)?, *field)))
+        else {
+            return Some(());
+        };
+
+        let is_engine = |engine: &EngineConfig| env_name_of(&engine.name) == env_name;
+
+        if !self.engines.iter().any(is_engine) {
+            self.engines
+                .push(EngineConfig::new(env_name.to_lowercase()));
+        }
+
+        for engine in self.engines.iter_mut().filter(|engine| is_engine(engine)) {
+            match field {
+                "_ENABLED" => engine.enabled = parse_env(value)?,
+                "_WEIGHT" => engine.weight = Some(parse_env(value)?),
+                "_TIMEOUT_MS" => engine.timeout_ms = Some(parse_env(value)?),
+                _ => engine.base_url = Some(value.to_string()),
+            }
+        }
+
+        Some(())
+    }
+
+    /// Builds a quaero instance from the configuration, constructing
+    /// each enabled engine through the specified registry.
+    pub fn build<C: HttpClient + Send + Sync + 'static>(
+        &self,
+        client: C,
+        registry: &EngineRegistry,
+    ) -> Result<Quaero<C>, ConfigError> {
+        let mut quaero = Quaero::empty(Arc::new(client));
+
+        if let Some(timeout_ms) = self.timeout_ms {
+            quaero = quaero.timeout(Duration::from_millis(timeout_ms));
+        }
+
+        quaero.reload_config(self, registry)?;
+
+        if let Some(cache_config) = self.cache {
+            let mut cache = MemoryCache::new(
+                cache_config.max_entries,
+                Duration::from_secs(cache_config.ttl_secs),
+            );
+
+            if let Some(window) = cache_config.stale_while_revalidate_secs {
+                cache = cache.stale_while_revalidate(Duration::from_secs(window));
+            }
+
+            quaero = quaero.cache(cache);
+        }
+
+        quaero = quaero.refiner_blend(RefinerBlend {
+            normalization: self.refiner.normalization.into(),
+            refined_weight: self.refiner.refined_weight,
+        });
+
+        if let Some(budget_ms) = self.refiner.budget_ms {
+            quaero = quaero.refine_budget(Duration::from_millis(budget_ms));
+        }
+
+        Ok(quaero)
+    }
+}
+
+impl<C: HttpClient + Send + Sync + 'static, const N: usize> Quaero<C, N> {
+    /// Atomically replaces the engines with those of the configuration, without
+    /// interrupting searches which are in flight. Disabled engines are kept so they
+    /// can be enabled at runtime with [Quaero::set_engines_enabled].
+    ///
+    /// Engines keep their identifier (and so their health, rate limit, retry policy,
+    /// client pool and middleware) when the configuration still lists an engine of the
+    /// same name. Only the engines and their settings are reloaded, the global timeout,
+    /// cache and refiner settings are only applied by [QuaeroConfig::build].
+    pub fn reload_config(
+        &self,
+        config: &QuaeroConfig,
+        registry: &EngineRegistry,
+    ) -> Result<(), ConfigError> {
+        // Engines are constructed up front, as the update can be retried if the engines
+        // are changed concurrently.
+        let built = config
+            .engines
+            .iter()
+            .map(|engine_config| {
+                let (engine, overrides) = engine_config.build(registry)?;
+                let overrides =
+                    overrides.map(|overrides| Arc::new(overrides) as Arc<dyn Middleware>);
+                Ok((engine_config, engine, overrides))
+            })
+            .collect::<Result<Vec<_>, ConfigError>>()?;
+
+        self.engines.rcu(|current| {
+            let mut engines = EngineSet::new(SmallVec::new());
+
+            for (engine_config, TaggedEngine(engine_id, engine, weight), overrides) in &built {
+                let engine_id = current
+                    .engines
+                    .iter()
+                    .map(|TaggedEngine(existing_id, ..)| existing_id)
+                    .find(|existing_id| {
+                        existing_id.name() == engine_id.name() && engines.get(existing_id).is_none()
+                    })
+                    .unwrap_or(engine_id)
+                    .clone();
+
+                if !engine_config.enabled {
+                    engines.disabled.insert(engine_id.clone());
+                }
+
+                if let Some(timeout_ms) = engine_config.timeout_ms {
+                    engines
+                        .timeouts
+                        .insert(engine_id.clone(), Duration::from_millis(timeout_ms));
+                }
+
+                if let Some(overrides) = overrides {
+                    engines
+                        .overrides
+                        .insert(engine_id.clone(), overrides.clone());
+                }
+
+                engines
+                    .engines
+                    .push(TaggedEngine(engine_id, engine.clone(), weight.clone()));
+            }
+
+            engines
+        });
+
+        Ok(())
+    }
+}
+
+impl EngineConfig {
+    /// Constructs the engine, along with the middleware which applies its base url and headers.
+    fn build(
+        &self,
+        registry: &EngineRegistry,
+    ) -> Result<(TaggedEngine, Option<EngineOverrides>), ConfigError> {
+        let mut engine = registry
+            .create(&self.name)
+            .ok_or_else(|| ConfigError::UnknownEngine(self.name.clone()))?;
+
+        if let Some(weight) = self.weight {
+            engine = engine.weight(weight);
+        }
+
+        let base_url = self.base_url()?;
+
+        let headers = self
+            .headers
+            .iter()
+            .map(
+                |(name, value)| match (name.parse::<HeaderName>(), value.parse::<HeaderValue>()) {
+                    (Ok(name), Ok(value)) => Ok((name, value)),
+                    _ => Err(ConfigError::InvalidHeader {
+                        engine: self.name.clone(),
+                        header: name.clone(),
+                    }),
+                },
+            )
+            .collect::<Result<HeaderMap, _>>()?;
+
+        let overrides = (base_url.is_some() || !headers.is_empty())
+            .then_some(EngineOverrides { base_url, headers });
+
+        Ok((engine, overrides))
+    }
+
+    /// Parses the base url, which needs to be absolute and can't have a query string.
+    fn base_url(&self) -> Result<Option<Uri>, ConfigError> {
+        let Some(url) = &self.base_url else {
+            return Ok(None);
+        };
+
+        match url.parse::<Uri>() {
+            Ok(uri)
+                if uri.scheme().is_some() && uri.authority().is_some() && uri.query().is_none() =>
+            {
+                Ok(Some(uri))
+            }
+            _ => Err(ConfigError::InvalidBaseUrl {
+                engine: self.name.clone(),
+                url: url.clone(),
+            }),
+        }
+    }
+}
+
+/// Middleware which applies an engine's configured base url and headers to its requests.
+struct EngineOverrides {
+    base_url: Option<Uri>,
+    headers: HeaderMap,
+}
+
+#[async_trait::async_trait]
+impl Middleware for EngineOverrides {
+    async fn on_request(
+        &self,
+        _engine_id: &EngineId,
+        _engine_name: &str,
+        request: &mut Request<Vec<u8>>,
+    ) -> Result<(), SearchError> {
+        request.headers_mut().extend(self.headers.clone());
+
+        if let Some(base_url) = &self.base_url {
+            let mut parts = request.uri().clone().into_parts();
+            parts.scheme = base_url.scheme().cloned();
+            parts.authority = base_url.authority().cloned();
+
+            let base_path = base_url.path().trim_end_matches('/');
+            if !base_path.is_empty() {
+                let path_and_query = parts
+                    .path_and_query
+                    .as_ref()
+                    .map_or("/", |path_and_query| path_and_query.as_str());
+
+                parts.path_and_query = Some(
+                    format!("{base_path}{path_and_query}")
+                        .parse()
+                        .map_err(|_| SearchError::RequestFailed)?,
+                );
+            }
+
+            *request.uri_mut() = Uri::from_parts(parts).map_err(|_| SearchError::RequestFailed)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the name of an engine as it appears in environment variables.
+fn env_name_of(name: &str) -> String {
+    name.chars()
+        .map(|ch| match ch.is_ascii_alphanumeric() {
+            true => ch.to_ascii_uppercase(),
+            false => '_',
+        })
+        .collect()
+}
+
+fn parse_env<T: std::str::FromStr>(value: &str) -> Option<T> {
+    value.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn apply_base_url(base_url: &str, url: &str) -> String {
+        let overrides = EngineOverrides {
+            base_url: Some(base_url.parse().unwrap()),
+            headers: HeaderMap::new(),
+        };
+
+        let mut request = Request::get(url).body(vec![]).unwrap();
+        let engine_id = "Google:V1StGXR8_Z".parse().unwrap();
+        overrides
+            .on_request(&engine_id, "Google", &mut request)
+            .await
+            .unwrap();
+
+        request.uri().to_string()
+    }
+
+    #[tokio::test]
+    async fn base_urls() {
+        assert_eq!(
+            apply_base_url(
+                "http://localhost:8080",
+                "https://www.google.com/search?q=rust"
+            )
+            .await,
+            "http://localhost:8080/search?q=rust"
+        );
+        assert_eq!(
+            apply_base_url(
+                "https://proxy.example/google/",
+                "https://www.google.com/search?q=rust"
+            )
+            .await,
+            "https://proxy.example/google/search?q=rust"
+        );
+    }
+
+    #[test]
+    fn invalid_base_urls() {
+        for base_url in ["www.google.com", "/search", "https://www.google.com/?hl=en"] {
+            let mut engine = EngineConfig::new("google");
+            engine.base_url = Some(base_url.to_string());
+
+            assert!(matches!(
+                engine.base_url(),
+                Err(ConfigError::InvalidBaseUrl { .. })
+            ));
+        }
+    }
+
+    fn env(vars: &[(&str, &str)]) -> impl Iterator<Item = (String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn toml_config() {
+        let config = QuaeroConfig::from_toml_str(
+            r#"
+            timeout_ms = 10000
+
+            [cache]
+            ttl_secs = 60
+
+            [refiner]
+            normalization = "z_score"
+            budget_ms = 500
+
+            [[engines]]
+            name = "google"
+            weight = 1.5
+            headers = { "Accept-Language" = "en-GB" }
+
+            [[engines]]
+            name = "yandex"
+            enabled = false
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.timeout_ms, Some(10000));
+        assert_eq!(
+            config.cache,
+            Some(CacheConfig {
+                ttl_secs: 60,
+                ..Default::default()
+            })
+        );
+        assert_eq!(config.refiner.normalization, NormalizationConfig::ZScore);
+        assert_eq!(config.refiner.budget_ms, Some(500));
+
+        let [google, yandex] = config.engines.as_slice() else {
+            panic!("expected two engines");
+        };
+        assert_eq!(google.weight, Some(1.5));
+        assert!(google.enabled);
+        assert_eq!(google.headers["Accept-Language"], "en-GB");
+        assert!(!yandex.enabled);
+    }
+
+    #[test]
+    fn json_config() {
+        let config = QuaeroConfig::from_json_str(
+            r#"{ "engines": [{ "name": "bing", "timeout_ms": 5000 }], "cache": {} }"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.timeout_ms, None);
+        assert_eq!(config.cache, Some(CacheConfig::default()));
+        assert_eq!(config.refiner, RefinerConfig::default());
+        assert_eq!(
+            config.engines,
+            [EngineConfig {
+                timeout_ms: Some(5000),
+                ..EngineConfig::new("bing")
+            }]
+        );
+
+        assert_eq!(
+            QuaeroConfig::from_json_str("{}").unwrap(),
+            QuaeroConfig::default()
+        );
+        assert!(matches!(
+            QuaeroConfig::from_json_str(r#"{ "timeout_ms": "soon" }"#),
+            Err(ConfigError::Json(_))
+        ));
+        assert!(matches!(
+            QuaeroConfig::from_toml_str("[[engines]]\nenabled = 1"),
+            Err(ConfigError::Toml(_))
+        ));
+        assert!(matches!(
+            QuaeroConfig::from_file("quaero.yaml"),
+            Err(ConfigError::UnknownFormat(_))
+        ));
+    }
+
+    #[test]
+    fn env_overrides() {
+        let config = QuaeroConfig {
+            engines: vec![EngineConfig::new("google"), EngineConfig::new("yandex")],
+            ..Default::default()
+        }
+        .apply_env(env(&[
+            ("QUAERO_TIMEOUT_MS", " 2500 "),
+            ("QUAERO_CACHE_TTL_SECS", "30"),
+            ("QUAERO_REFINER_NORMALIZATION", "Z_SCORE"),
+            ("QUAERO_ENGINE_YANDEX_ENABLED", "false"),
+            ("QUAERO_ENGINE_GOOGLE_WEIGHT", "2"),
+            ("QUAERO_ENGINE_GOOGLE_BASE_URL", "https://www.google.co.uk"),
+            ("QUAERO_ENGINE_MOJEEK_TIMEOUT_MS", "1000"),
+            // Unrelated variables are ignored.
+            ("QUAERO_UNKNOWN", "1"),
+            ("QUAERO_ENGINE_GOOGLE_UNKNOWN", "1"),
+            ("PATH", "/usr/bin"),
+        ]))
+        .unwrap();
+
+        assert_eq!(config.timeout_ms, Some(2500));
+        assert_eq!(config.cache.unwrap().ttl_secs, 30);
+        assert_eq!(config.refiner.normalization, NormalizationConfig::ZScore);
+
+        let [google, yandex, mojeek] = config.engines.as_slice() else {
+            panic!("expected three engines");
+        };
+        assert_eq!(google.weight, Some(2.));
+        assert_eq!(google.base_url.as_deref(), Some("https://www.google.co.uk"));
+        assert!(!yandex.enabled);
+
+        // Engines which aren't configured yet are appended.
+        assert_eq!(mojeek.name, "mojeek");
+        assert_eq!(mojeek.timeout_ms, Some(1000));
+    }
+
+    #[test]
+    fn env_engine_names_with_punctuation() {
+        let config = QuaeroConfig {
+            engines: vec![
+                EngineConfig::new("brave-search"),
+                EngineConfig::new("Brave Search"),
+                EngineConfig::new("brave"),
+            ],
+            ..Default::default()
+        }
+        .apply_env(env(&[("QUAERO_ENGINE_BRAVE_SEARCH_ENABLED", "false")]))
+        .unwrap();
+
+        // Every engine whose name maps to the variable's is overridden.
+        let enabled: Vec<bool> = config.engines.iter().map(|engine| engine.enabled).collect();
+        assert_eq!(enabled, [false, false, true]);
+        assert_eq!(env_name_of("my.engine-2"), "MY_ENGINE_2");
+    }
+
+    #[test]
+    fn invalid_env_values() {
+        for (name, value) in [
+            ("QUAERO_TIMEOUT_MS", "-1"),
+            ("QUAERO_CACHE_MAX_ENTRIES", "lots"),
+            ("QUAERO_REFINER_NORMALIZATION", "softmax"),
+            ("QUAERO_REFINER_WEIGHT", ""),
+            ("QUAERO_ENGINE_YANDEX_ENABLED", "no"),
+            ("QUAERO_ENGINE_GOOGLE_TIMEOUT_MS", "1.5"),
+        ] {
+            let result = QuaeroConfig::default().apply_env(env(&[(name, value)]));
+
+            assert!(
+                matches!(
+                    &result,
+                    Err(ConfigError::InvalidEnvVar { name: err_name, value: err_value })
+                        if err_name == name && err_value == value
+                ),
+                "{name}={value} should be invalid"
+            );
+        }
+    }
+}
@@ -0,0 +1,316 @@
+//! Collapses search results which are near-duplicates of each other, such as the same
+//! article syndicated across multiple hosts or the mobile and amp variants of a page.
+
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use quaero_shared::models::{
+    sanitized_url::is_registrable,
+    search::{SearchResultKind, SearchResultWithMetadata},
+};
+
+/// Subdomains which usually serve an alternate version of the same page.
+const VARIANT_SUBDOMAINS: [&str; 4] = ["www.", "m.", "mobile.", "amp."];
+
+/// Configures how near-duplicate search results are detected.
+#[derive(Debug, Clone, Copy)]
+pub struct NearDuplicates {
+    /// Collapses results whose urls only differ by variant subdomains
+    /// (such as `www.`, `m.` or `amp.`) or amp path segments and parameters.
+    pub canonical_urls: bool,
+
+    /// The maximum amount of bits the SimHashes of two results' titles and summaries
+    /// can differ by for them to be collapsed. `None` disables content-based detection.
+    pub max_distance: Option<u32>,
+
+    /// How many words a result's title and summary need to contain before its
+    /// content is compared, as short snippets are too similar to compare reliably.
+    pub min_words: usize,
+}
+
+impl NearDuplicates {
+    /// Never collapses results, other than those with identical strict urls.
+    pub fn none() -> Self {
+        Self {
+            canonical_urls: false,
+            max_distance: None,
+            min_words: 0,
+        }
+    }
+}
+
+impl Default for NearDuplicates {
+    fn default() -> Self {
+        Self {
+            canonical_urls: true,
+            max_distance: Some(3),
+            min_words: 8,
+        }
+    }
+}
+
+/// Collapses near-duplicate results into whichever of them has the highest lexical score.
+pub(crate) fn collapse_near_duplicates(
+    results: Vec<SearchResultWithMetadata>,
+    near_duplicates: NearDuplicates,
+) -> Box<[SearchResultWithMetadata]> {
+    let mut results = match near_duplicates.canonical_urls {
+        true => collapse_canonical_urls(results),
+        false => results,
+    };
+
+    if let Some(max_distance) = near_duplicates.max_distance {
+        results = collapse_similar_content(results, max_distance, near_duplicates.min_words);
+    }
+
+    results.into_boxed_slice()
+}
+
+fn collapse_canonical_urls(
+    results: Vec<SearchResultWithMetadata>,
+) -> Vec<SearchResultWithMetadata> {
+    let mut collapsed: Vec<SearchResultWithMetadata> = Vec::with_capacity(results.len());
+    let mut canonical_idxs: HashMap<String, usize> = HashMap::with_capacity(results.len());
+
+    for result in results {
+        match canonical_idxs.entry(canonical_url(&result.url_key)) {
+            Entry::Occupied(entry) => collapse_into(&mut collapsed[*entry.get()], result),
+            Entry::Vacant(entry) => {
+                entry.insert(collapsed.len());
+                collapsed.push(result);
+            }
+        }
+    }
+
+    collapsed
+}
+
+fn collapse_similar_content(
+    results: Vec<SearchResultWithMetadata>,
+    max_distance: u32,
+    min_words: usize,
+) -> Vec<SearchResultWithMetadata> {
+    let mut collapsed: Vec<(Option<u64>, SearchResultWithMetadata)> =
+        Vec::with_capacity(results.len());
+
+    for result in results {
+        let fingerprint = simhash(&result, min_words);
+
+        let duplicate_of = fingerprint.and_then(|fingerprint| {
+            collapsed.iter().position(|(existing_fingerprint, _)| {
+                existing_fingerprint.is_some_and(|existing_fingerprint| {
+                    (existing_fingerprint ^ fingerprint).count_ones() <= max_distance
+                })
+            })
+        });
+
+        match duplicate_of {
+            Some(idx) => collapse_into(&mut collapsed[idx].1, result),
+            None => collapsed.push((fingerprint, result)),
+        }
+    }
+
+    collapsed.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Merges a duplicate into a result, keeping whichever has the higher lexical score as the primary.
+fn collapse_into(result: &mut SearchResultWithMetadata, mut duplicate: SearchResultWithMetadata) {
+    if duplicate.lexical_score > result.lexical_score {
+        std::mem::swap(result, &mut duplicate);
+    }
+
+    result.merge_near_duplicate(duplicate);
+}
+
+/// Strips the parts of a strict url which commonly differ between variants of the same page.
+fn canonical_url(url_key: &str) -> String {
+    let url = url_key
+        .split_once("://")
+        .map_or(url_key, |(_protocol, rest)| rest);
+
+    let (url, search_params) = url.split_once('?').unwrap_or((url, ""));
+    let (mut host, path) = url.split_once('/').unwrap_or((url, ""));
+
+    // Subdomains are only stripped while a registrable domain is left,
+    // so `mobile.de` isn't collapsed into `de`.
+    while let Some(stripped_host) = VARIANT_SUBDOMAINS
+        .iter()
+        .find_map(|subdomain| host.strip_prefix(subdomain))
+        .filter(|stripped_host| {
+            let (hostname, _port) = stripped_host.split_once(':').unwrap_or((stripped_host, ""));
+            is_registrable(hostname)
+        })
+    {
+        host = stripped_host;
+    }
+
+    let path = path
+        .split('/')
+        .filter(|segment| !segment.is_empty() && *segment != "amp")
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let search_params = search_params
+        .split('&')
+        .filter(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            !pair.is_empty() && name != "amp" && value != "amp"
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+
+    format!("{host}/{path}?{search_params}")
+}
+
+/// Computes a SimHash of the pairs of consecutive words in a result's title and summary,
+/// as long as it contains enough words.
+fn simhash(result: &SearchResultWithMetadata, min_words: usize) -> Option<u64> {
+    // Different images and videos often share near identical titles,
+    // so only their urls are compared.
+    if matches!(
+        result.search_result.kind,
+        SearchResultKind::Image(_) | SearchResultKind::Video(_)
+    ) {
+        return None;
+    }
+
+    let text = format!(
+        "{} {}",
+        result.search_result.title, result.search_result.summary
+    )
+    .to_lowercase();
+
+    let words: Vec<&str> = text
+        .split(|char: char| !char.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    if words.len() < min_words.max(2) {
+        return None;
+    }
+
+    let mut bit_weights = [0i32; 64];
+
+    for shingle in words.windows(2) {
+        let mut hasher = DefaultHasher::new();
+        shingle.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        for (bit, weight) in bit_weights.iter_mut().enumerate() {
+            match (hash >> bit) & 1 {
+                1 => *weight += 1,
+                _ => *weight -= 1,
+            }
+        }
+    }
+
+    Some(
+        bit_weights
+            .iter()
+            .enumerate()
+            .filter(|(_, weight)| **weight > 0)
+            .fold(0, |fingerprint, (bit, _)| fingerprint | (1 << bit)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use quaero_shared::models::search::SearchResult;
+
+    use super::*;
+
+    fn result(title: &str, url: &str, summary: &str) -> SearchResultWithMetadata {
+        let (url_key, search_result) =
+            SearchResult::new(title.to_string(), url.to_string(), summary.to_string());
+
+        SearchResultWithMetadata::new(
+            url_key,
+            search_result,
+            &"Google:V1StGXR8_Z".parse().unwrap(),
+            0,
+        )
+    }
+
+    #[test]
+    fn canonical_urls() {
+        assert_eq!(
+            canonical_url("https://www.m.example.com/amp/news/article?amp=1&id=2"),
+            "example.com/news/article?id=2"
+        );
+        assert_eq!(
+            canonical_url("amp.example.co.uk:8080/news/?amp"),
+            "example.co.uk:8080/news?"
+        );
+        assert_eq!(
+            canonical_url("https://example.com/news/article?id=2"),
+            canonical_url("https://mobile.example.com/news/article/?id=2&amp=true")
+        );
+    }
+
+    #[test]
+    fn keeps_registrable_domains() {
+        // Stripping these would leave a bare public suffix.
+        assert_eq!(canonical_url("mobile.de/auto"), "mobile.de/auto?");
+        assert_eq!(canonical_url("www.m.co.uk/"), "m.co.uk/?");
+        assert_eq!(canonical_url("amp.dev/docs"), "amp.dev/docs?");
+    }
+
+    #[test]
+    fn collapses_variant_urls() {
+        let collapsed = collapse_near_duplicates(
+            vec![
+                result("Article", "https://example.com/article", ""),
+                result("Article", "https://m.example.com/article", ""),
+                result("Cars", "https://mobile.de/", ""),
+                result("Cars", "https://m.mobile.de/", ""),
+            ],
+            NearDuplicates {
+                max_distance: None,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(collapsed.len(), 2);
+        assert_eq!(collapsed[0].also_at.len(), 1);
+        assert_eq!(collapsed[1].also_at.len(), 1);
+    }
+
+    #[test]
+    fn simhash_threshold() {
+        let summary = "the quick brown fox jumps over the lazy dog near the river bank today";
+        let original = result("Fox news", "https://a.example/fox", summary);
+        let syndicated = result("Fox news", "https://b.example/fox", &format!("{summary}!"));
+        let different = result(
+            "Rust release",
+            "https://c.example/rust",
+            "a new version of the rust programming language was released with faster builds",
+        );
+
+        let original_hash = simhash(&original, 8).unwrap();
+        assert_eq!(simhash(&syndicated, 8), Some(original_hash));
+        assert!((simhash(&different, 8).unwrap() ^ original_hash).count_ones() > 3);
+
+        // Snippets with too few words aren't compared.
+        assert_eq!(
+            simhash(&result("Fox", "https://d.example", "news"), 8),
+            None
+        );
+
+        let collapse = |max_distance| {
+            collapse_near_duplicates(
+                vec![original.clone(), syndicated.clone(), different.clone()],
+                NearDuplicates {
+                    canonical_urls: false,
+                    max_distance: Some(max_distance),
+                    min_words: 8,
+                },
+            )
+            .len()
+        };
+
+        assert_eq!(collapse(3), 2);
+        assert_eq!(collapse(64), 1);
+    }
+}
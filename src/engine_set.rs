@@ -0,0 +1,112 @@
+//! The set of engines a quaero instance queries, which can be changed while searches are in flight.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use smallvec::SmallVec;
+
+use quaero_shared::models::{
+    engine::{EngineId, EngineSelector, EngineWeight, TaggedEngine},
+    search::SearchCategory,
+};
+
+use crate::{health::EngineHealth, middleware::Middleware};
+
+/// Information about one of a quaero instance's engines.
+#[derive(Debug, Clone)]
+pub struct EngineInfo {
+    /// The engine's identifier.
+    pub engine_id: EngineId,
+
+    /// The engine's name.
+    pub name: String,
+
+    /// The engine's homepage url.
+    pub homepage: &'static str,
+
+    /// The verticals the engine can search in.
+    pub categories: &'static [SearchCategory],
+
+    /// How much the engine's results are trusted, which can be adjusted at runtime.
+    pub weight: EngineWeight,
+
+    /// Whether the engine is queried. Disabled engines are skipped by every search.
+    pub enabled: bool,
+
+    /// The maximum amount of time the engine has to fetch its results.
+    pub timeout: Duration,
+
+    /// The engine's health, including whether it is temporarily disabled.
+    pub health: EngineHealth,
+}
+
+/// A snapshot of a quaero instance's engines along with their runtime settings.
+/// Searches hold onto the snapshot they started with, so changes only apply to later searches.
+#[derive(Clone)]
+pub(crate) struct EngineSet<const N: usize> {
+    pub(crate) engines: SmallVec<[TaggedEngine; N]>,
+    pub(crate) disabled: HashSet<EngineId>,
+    pub(crate) timeouts: HashMap<EngineId, Duration>,
+    // Middleware which applies the base url and headers an engine was configured with.
+    pub(crate) overrides: HashMap<EngineId, Arc<dyn Middleware>>,
+}
+
+impl<const N: usize> EngineSet<N> {
+    pub(crate) fn new(engines: SmallVec<[TaggedEngine; N]>) -> Self {
+        Self {
+            engines,
+            disabled: HashSet::new(),
+            timeouts: HashMap::new(),
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// Returns the engines which haven't been disabled.
+    pub(crate) fn enabled(&self) -> impl Iterator<Item = &TaggedEngine> {
+        self.engines
+            .iter()
+            .filter(|TaggedEngine(engine_id, ..)| !self.disabled.contains(engine_id))
+    }
+
+    /// Returns the maximum amount of time the specified engine has to fetch its results.
+    pub(crate) fn timeout_for(&self, engine_id: &EngineId, default: Duration) -> Duration {
+        self.timeouts.get(engine_id).copied().unwrap_or(default)
+    }
+
+    /// Returns the engine with the specified identifier.
+    pub(crate) fn get(&self, engine_id: &EngineId) -> Option<&TaggedEngine> {
+        self.engines
+            .iter()
+            .find(|TaggedEngine(existing_id, ..)| existing_id == engine_id)
+    }
+
+    /// Returns the identifiers of the selected engines.
+    pub(crate) fn select(&self, selector: &EngineSelector) -> Vec<EngineId> {
+        self.engines
+            .iter()
+            .map(|TaggedEngine(engine_id, ..)| engine_id)
+            .filter(|engine_id| selector.matches(engine_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Creates a copy of this set with different engines. The settings of
+    /// engines which are in both sets are kept, while the rest are dropped.
+    pub(crate) fn with_engines(&self, engines: SmallVec<[TaggedEngine; N]>) -> Self {
+        let contains = |engine_id: &EngineId| {
+            engines
+                .iter()
+                .any(|TaggedEngine(existing_id, ..)| existing_id == engine_id)
+        };
+
+        let mut set = self.clone();
+        set.disabled.retain(|engine_id| contains(engine_id));
+        set.timeouts.retain(|engine_id, _| contains(engine_id));
+        set.overrides.retain(|engine_id, _| contains(engine_id));
+        set.engines = engines;
+        set
+    }
+}
@@ -0,0 +1,433 @@
+//! Tracks the health of each engine and temporarily disables ones which keep getting blocked.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tracing::warn;
+
+use quaero_shared::models::{engine::EngineId, search::SearchError};
+
+/// How many of the most recent outcomes are kept for each engine.
+const RECENT_OUTCOMES_LEN: usize = 20;
+
+/// How many of the most recent successful latencies are kept for each engine.
+const RECENT_LATENCIES_LEN: usize = 50;
+
+/// Configures when an engine is temporarily disabled and for how long.
+#[derive(Debug, Clone, Copy)]
+pub struct CircuitBreaker {
+    /// How many consecutive captchas, blocks or timeouts it takes to disable an engine.
+    pub failure_threshold: u32,
+
+    /// How long an engine is disabled for the first time it trips.
+    pub base_cooldown: Duration,
+
+    /// The longest an engine can be disabled for. The cooldown doubles each
+    /// time an engine trips in a row, up until this limit.
+    pub max_cooldown: Duration,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 3,
+            base_cooldown: Duration::from_secs(30),
+            max_cooldown: Duration::from_secs(30 * 60),
+        }
+    }
+}
+
+/// Whether an engine is currently being queried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// The engine is healthy and is queried as normal.
+    Closed,
+
+    /// The engine is temporarily disabled and is skipped.
+    Open,
+
+    /// The engine's cooldown has passed and a single search is allowed
+    /// through to probe whether it has recovered.
+    HalfOpen,
+}
+
+/// A snapshot of an engine's health.
+#[derive(Debug, Clone)]
+pub struct EngineHealth {
+    /// Whether the engine is currently being queried.
+    pub state: CircuitState,
+
+    /// How long until the engine is probed again, if it is disabled.
+    pub disabled_for: Option<Duration>,
+
+    /// How many captchas, blocks or timeouts the engine has returned in a row.
+    pub consecutive_failures: u32,
+
+    /// The outcomes of the engine's most recent searches, oldest first.
+    pub recent_outcomes: Vec<Result<(), SearchError>>,
+}
+
+impl Default for EngineHealth {
+    fn default() -> Self {
+        Self {
+            state: CircuitState::Closed,
+            disabled_for: None,
+            consecutive_failures: 0,
+            recent_outcomes: Vec::new(),
+        }
+    }
+}
+
+/// Permission to query an engine, which is passed back when recording the outcome.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Permit {
+    /// The engine is healthy and is queried as normal.
+    Query,
+
+    /// The engine is being probed to check whether it has recovered.
+    Probe(u64),
+}
+
+struct HealthRecord {
+    state: CircuitState,
+    consecutive_failures: u32,
+    // How many times the engine has been disabled in a row.
+    trips: u32,
+    reopens_at: Instant,
+    probe_started_at: Option<Instant>,
+    // Identifies the current probe, so outcomes of other requests can't be mistaken for it.
+    probe_id: u64,
+    recent_outcomes: VecDeque<Result<(), SearchError>>,
+    recent_latencies: VecDeque<Duration>,
+}
+
+impl Default for HealthRecord {
+    fn default() -> Self {
+        Self {
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            trips: 0,
+            reopens_at: Instant::now(),
+            probe_started_at: None,
+            probe_id: 0,
+            recent_outcomes: VecDeque::with_capacity(RECENT_OUTCOMES_LEN),
+            recent_latencies: VecDeque::with_capacity(RECENT_LATENCIES_LEN),
+        }
+    }
+}
+
+/// Tracks the health of each engine across searches.
+#[derive(Default)]
+pub(crate) struct HealthTracker {
+    pub(crate) circuit_breaker: CircuitBreaker,
+    records: Mutex<HashMap<EngineId, HealthRecord>>,
+}
+
+impl HealthTracker {
+    pub(crate) fn new(circuit_breaker: CircuitBreaker) -> Self {
+        Self {
+            circuit_breaker,
+            records: Mutex::default(),
+        }
+    }
+
+    /// Returns whether the engine should be queried, moving it into
+    /// the half open state if its cooldown has passed.
+    pub(crate) fn permit(&self, engine_id: &EngineId) -> Option<Permit> {
+        let mut records = self.records.lock().unwrap_or_else(|err| err.into_inner());
+
+        let Some(record) = records.get_mut(engine_id) else {
+            return Some(Permit::Query);
+        };
+
+        let now = Instant::now();
+
+        match record.state {
+            CircuitState::Closed => Some(Permit::Query),
+            CircuitState::Open if now < record.reopens_at => None,
+            CircuitState::Open | CircuitState::HalfOpen => {
+                // Only one probe is allowed at a time, unless the previous one
+                // was abandoned without reporting back.
+                let is_probing = record.probe_started_at.is_some_and(|started_at| {
+                    now.duration_since(started_at) < self.circuit_breaker.base_cooldown
+                });
+
+                if is_probing {
+                    return None;
+                }
+
+                record.state = CircuitState::HalfOpen;
+                record.probe_started_at = Some(now);
+                record.probe_id += 1;
+                Some(Permit::Probe(record.probe_id))
+            }
+        }
+    }
+
+    /// Records the outcome of querying an engine with the permit it was given.
+    pub(crate) fn record<T>(
+        &self,
+        engine_id: &EngineId,
+        permit: Permit,
+        outcome: &Result<T, SearchError>,
+    ) {
+        let mut records = self.records.lock().unwrap_or_else(|err| err.into_inner());
+        let record = records.entry(engine_id.clone()).or_default();
+
+        if record.recent_outcomes.len() == RECENT_OUTCOMES_LEN {
+            record.recent_outcomes.pop_front();
+        }
+        record
+            .recent_outcomes
+            .push_back(outcome.as_ref().map(|_| ()).map_err(Clone::clone));
+
+        // Requests which were already in flight when the circuit opened can still report
+        // back, but only the half open probe decides whether the engine has recovered.
+        let is_probe = permit == Permit::Probe(record.probe_id);
+
+        match record.state {
+            CircuitState::Open => return,
+            CircuitState::HalfOpen if !is_probe => return,
+            _ => {}
+        }
+
+        record.probe_started_at = None;
+
+        let trips_circuit = matches!(
+            outcome,
+            Err(SearchError::Captcha | SearchError::Blocked | SearchError::Timeout)
+        );
+
+        if !trips_circuit {
+            // Any other outcome means the engine is at least reachable.
+            record.state = CircuitState::Closed;
+            record.consecutive_failures = 0;
+            record.trips = 0;
+            return;
+        }
+
+        record.consecutive_failures += 1;
+
+        let should_open = record.state == CircuitState::HalfOpen
+            || record.consecutive_failures >= self.circuit_breaker.failure_threshold;
+
+        if should_open {
+            let cooldown = self.cooldown(record.trips);
+
+            warn!(
+                "[{:?}] Temporarily disabling engine for {:?}.",
+                engine_id, cooldown
+            );
+
+            record.state = CircuitState::Open;
+            record.reopens_at = Instant::now() + cooldown;
+            record.trips += 1;
+        }
+    }
+
+    /// Records how long a successful request to an engine took.
+    pub(crate) fn record_latency(&self, engine_id: &EngineId, latency: Duration) {
+        let mut records = self.records.lock().unwrap_or_else(|err| err.into_inner());
+        let record = records.entry(engine_id.clone()).or_default();
+
+        if record.recent_latencies.len() == RECENT_LATENCIES_LEN {
+            record.recent_latencies.pop_front();
+        }
+        record.recent_latencies.push_back(latency);
+    }
+
+    /// Returns the specified percentile (between `0.0` and `1.0`) of the engine's
+    /// recent latencies, as long as enough of them have been recorded.
+    pub(crate) fn latency_percentile(
+        &self,
+        engine_id: &EngineId,
+        percentile: f32,
+        min_samples: usize,
+    ) -> Option<Duration> {
+        let records = self.records.lock().unwrap_or_else(|err| err.into_inner());
+        let record = records.get(engine_id)?;
+
+        if record.recent_latencies.is_empty() || record.recent_latencies.len() < min_samples {
+            return None;
+        }
+
+        let mut latencies: Vec<Duration> = record.recent_latencies.iter().copied().collect();
+        latencies.sort_unstable();
+
+        let idx = ((latencies.len() - 1) as f32 * percentile.clamp(0., 1.)).round() as usize;
+        latencies.get(idx).copied()
+    }
+
+    /// Returns a snapshot of the engine's health.
+    pub(crate) fn health(&self, engine_id: &EngineId) -> EngineHealth {
+        let records = self.records.lock().unwrap_or_else(|err| err.into_inner());
+
+        let Some(record) = records.get(engine_id) else {
+            return EngineHealth::default();
+        };
+
+        let disabled_for = match record.state {
+            CircuitState::Open => Some(record.reopens_at.saturating_duration_since(Instant::now())),
+            _ => None,
+        };
+
+        EngineHealth {
+            state: record.state,
+            disabled_for,
+            consecutive_failures: record.consecutive_failures,
+            recent_outcomes: record.recent_outcomes.iter().cloned().collect(),
+        }
+    }
+
+    fn cooldown(&self, trips: u32) -> Duration {
+        let CircuitBreaker {
+            base_cooldown,
+            max_cooldown,
+            ..
+        } = self.circuit_breaker;
+
+        base_cooldown
+            .saturating_mul(2u32.saturating_pow(trips))
+            .min(max_cooldown)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker() -> HealthTracker {
+        HealthTracker::new(CircuitBreaker {
+            failure_threshold: 2,
+            base_cooldown: Duration::from_millis(20),
+            max_cooldown: Duration::from_secs(1),
+        })
+    }
+
+    fn engine_id() -> EngineId {
+        "Google:V1StGXR8_Z".parse().unwrap()
+    }
+
+    fn record(tracker: &HealthTracker, outcome: Result<(), SearchError>) {
+        tracker.record(&engine_id(), Permit::Query, &outcome);
+    }
+
+    fn trip(tracker: &HealthTracker) {
+        record(tracker, Err(SearchError::Captcha));
+        record(tracker, Err(SearchError::Blocked));
+        assert_eq!(tracker.health(&engine_id()).state, CircuitState::Open);
+    }
+
+    fn wait_for_cooldown(tracker: &HealthTracker) {
+        let disabled_for = tracker.health(&engine_id()).disabled_for.unwrap();
+        std::thread::sleep(disabled_for + Duration::from_millis(5));
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let tracker = tracker();
+
+        record(&tracker, Err(SearchError::Captcha));
+        assert_eq!(tracker.health(&engine_id()).state, CircuitState::Closed);
+        assert_eq!(tracker.permit(&engine_id()), Some(Permit::Query));
+
+        // Failures which don't indicate the engine is blocking us reset the count.
+        record(&tracker, Err(SearchError::NoResultsFound));
+        record(&tracker, Err(SearchError::Timeout));
+        assert_eq!(tracker.health(&engine_id()).state, CircuitState::Closed);
+
+        record(&tracker, Err(SearchError::Timeout));
+        assert_eq!(tracker.health(&engine_id()).state, CircuitState::Open);
+        assert_eq!(tracker.permit(&engine_id()), None);
+    }
+
+    #[test]
+    fn ignores_in_flight_outcomes_while_open() {
+        let tracker = tracker();
+        trip(&tracker);
+
+        let reopens_at = tracker.records.lock().unwrap()[&engine_id()].reopens_at;
+
+        // A late failure doesn't re-trip the circuit or extend its cooldown...
+        record(&tracker, Err(SearchError::Captcha));
+        let health = tracker.health(&engine_id());
+        assert_eq!(health.state, CircuitState::Open);
+        assert_eq!(
+            tracker.records.lock().unwrap()[&engine_id()].reopens_at,
+            reopens_at
+        );
+        assert_eq!(tracker.records.lock().unwrap()[&engine_id()].trips, 1);
+
+        // ...and a late success doesn't close it.
+        record(&tracker, Ok(()));
+        let health = tracker.health(&engine_id());
+        assert_eq!(health.state, CircuitState::Open);
+        assert_eq!(health.recent_outcomes.len(), 4);
+    }
+
+    #[test]
+    fn closes_after_successful_probe() {
+        let tracker = tracker();
+        trip(&tracker);
+        wait_for_cooldown(&tracker);
+
+        let permit = tracker.permit(&engine_id()).unwrap();
+        assert!(matches!(permit, Permit::Probe(_)));
+        assert_eq!(tracker.health(&engine_id()).state, CircuitState::HalfOpen);
+
+        // Only one probe is let through at a time.
+        assert_eq!(tracker.permit(&engine_id()), None);
+
+        tracker.record(&engine_id(), permit, &Ok::<_, SearchError>(()));
+        let health = tracker.health(&engine_id());
+        assert_eq!(health.state, CircuitState::Closed);
+        assert_eq!(health.consecutive_failures, 0);
+        assert_eq!(tracker.permit(&engine_id()), Some(Permit::Query));
+    }
+
+    #[test]
+    fn ignores_in_flight_outcomes_while_half_open() {
+        let tracker = tracker();
+        trip(&tracker);
+        wait_for_cooldown(&tracker);
+
+        let permit = tracker.permit(&engine_id()).unwrap();
+
+        // A request which was in flight before the probe started doesn't close the circuit...
+        record(&tracker, Ok(()));
+        {
+            let record = &tracker.records.lock().unwrap()[&engine_id()];
+            assert_eq!(record.state, CircuitState::HalfOpen);
+            assert_eq!(record.trips, 1);
+            assert!(record.probe_started_at.is_some());
+        }
+
+        // ...and neither does an earlier probe's.
+        tracker.record(&engine_id(), Permit::Probe(0), &Ok::<_, SearchError>(()));
+        assert_eq!(tracker.health(&engine_id()).state, CircuitState::HalfOpen);
+
+        tracker.record(&engine_id(), permit, &Err::<(), _>(SearchError::Captcha));
+        assert_eq!(tracker.health(&engine_id()).state, CircuitState::Open);
+    }
+
+    #[test]
+    fn reopens_after_failed_probe() {
+        let tracker = tracker();
+        trip(&tracker);
+        wait_for_cooldown(&tracker);
+
+        let permit = tracker.permit(&engine_id()).unwrap();
+        tracker.record(&engine_id(), permit, &Err::<(), _>(SearchError::Blocked));
+
+        let health = tracker.health(&engine_id());
+        assert_eq!(health.state, CircuitState::Open);
+        assert_eq!(tracker.permit(&engine_id()), None);
+
+        // The cooldown doubles each time the engine trips in a row.
+        let record = &tracker.records.lock().unwrap()[&engine_id()];
+        assert_eq!(record.trips, 2);
+        assert!(health.disabled_for.unwrap() > Duration::from_millis(20));
+    }
+}
@@ -28,17 +28,63 @@
 #![warn(missing_docs)]
 
 use anyhttp::HttpClient;
+use arc_swap::ArcSwap;
+use futures_core::Stream;
 use smallvec::SmallVec;
-use std::{sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 mod aggregator;
-use aggregator::aggregate_results;
+use aggregator::{SearchPlan, aggregate_results, aggregate_results_stream};
+
+pub mod cache;
+
+pub mod client_pool;
+use client_pool::ClientPool;
+
+#[cfg(feature = "config")]
+pub mod config;
+
+pub mod dedup;
+use dedup::NearDuplicates;
+
+pub mod engine_set;
+use engine_set::{EngineInfo, EngineSet};
+
+pub mod health;
+use health::{CircuitBreaker, EngineHealth, HealthTracker};
+
+pub mod metrics;
+use metrics::Metrics;
+
+pub mod middleware;
+use middleware::Middleware;
+
+pub mod rate_limit;
+use rate_limit::{OverBudget, RateLimit, RateLimiter};
+
+pub mod ranking;
+use ranking::Bm25;
+
+pub mod refine;
+use refine::RefinerBlend;
+
+pub mod retry;
+use retry::RetryPolicy;
+
+pub mod session;
+use session::SearchSession;
 
 use quaero_shared::models::{
-    engine::TaggedEngine,
+    engine::{EngineId, EngineSelector, EngineWeight, TaggedEngine},
+    ranker::Ranker,
     sanitized_url::PUBLIC_SUFFIX_LIST,
     score_refiner::ScoreRefiner,
-    search::{SearchOptions, SearchResponse},
+    search::{CacheStatus, RankFusion, SearchEvent, SearchOptions, SearchResponse},
+    search_cache::{SearchCache, cache_key},
 };
 
 pub use quaero_shared::*;
@@ -47,10 +93,53 @@ const DEFAULT_TIMEOUT_SECS: u64 = 15;
 
 /// The main struct of this library. Used to store engines to query and aggregate later.
 pub struct Quaero<C: HttpClient + 'static, const N: usize = 10> {
-    client: Arc<C>,
-    engines: SmallVec<[TaggedEngine; N]>,
-    score_refiner: Option<Box<dyn ScoreRefiner>>,
+    clients: Arc<ClientPool<C>>,
+    engine_clients: HashMap<EngineId, Arc<ClientPool<C>>>,
+    // Shared with every clone, and swapped out whenever the engines are changed.
+    engines: Arc<ArcSwap<EngineSet<N>>>,
+    score_refiners: Vec<Arc<dyn ScoreRefiner>>,
+    refiner_blend: RefinerBlend,
+    refine_budget: Option<Duration>,
+    ranker: Arc<dyn Ranker>,
+    rank_fusion: RankFusion,
+    near_duplicates: NearDuplicates,
     timeout: Duration,
+    cache: Option<Arc<dyn SearchCache>>,
+    // Keys of stale cached responses which are currently being refreshed.
+    revalidating: Arc<Mutex<HashSet<String>>>,
+    health: Arc<HealthTracker>,
+    rate_limiter: Arc<RateLimiter>,
+    metrics: Option<Arc<dyn Metrics>>,
+    retry_policy: Arc<RetryPolicy>,
+    engine_retry_policies: HashMap<EngineId, Arc<RetryPolicy>>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    engine_middleware: HashMap<EngineId, Vec<Arc<dyn Middleware>>>,
+}
+
+impl<C: HttpClient + 'static, const N: usize> Clone for Quaero<C, N> {
+    fn clone(&self) -> Self {
+        Self {
+            clients: self.clients.clone(),
+            engine_clients: self.engine_clients.clone(),
+            engines: self.engines.clone(),
+            score_refiners: self.score_refiners.clone(),
+            refiner_blend: self.refiner_blend,
+            refine_budget: self.refine_budget,
+            ranker: self.ranker.clone(),
+            rank_fusion: self.rank_fusion,
+            near_duplicates: self.near_duplicates,
+            timeout: self.timeout,
+            cache: self.cache.clone(),
+            revalidating: self.revalidating.clone(),
+            health: self.health.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+            metrics: self.metrics.clone(),
+            retry_policy: self.retry_policy.clone(),
+            engine_retry_policies: self.engine_retry_policies.clone(),
+            middleware: self.middleware.clone(),
+            engine_middleware: self.engine_middleware.clone(),
+        }
+    }
 }
 
 impl<C: HttpClient + Send + Sync + 'static, const N: usize> Quaero<C, N> {
@@ -59,30 +148,277 @@ impl<C: HttpClient + Send + Sync + 'static, const N: usize> Quaero<C, N> {
         init();
 
         Quaero {
-            client: client.into(),
-            engines: engines.into(),
-            score_refiner: None,
+            clients: Arc::new(ClientPool::new([client])),
+            engine_clients: HashMap::new(),
+            engines: Arc::new(ArcSwap::from_pointee(EngineSet::new(engines.into()))),
+            score_refiners: Vec::new(),
+            refiner_blend: RefinerBlend::default(),
+            refine_budget: None,
+            ranker: Arc::new(Bm25::default()),
+            rank_fusion: RankFusion::default(),
+            near_duplicates: NearDuplicates::default(),
             timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            cache: None,
+            revalidating: Arc::default(),
+            health: Arc::default(),
+            rate_limiter: Arc::default(),
+            metrics: None,
+            retry_policy: Arc::default(),
+            engine_retry_policies: HashMap::new(),
+            middleware: Vec::new(),
+            engine_middleware: HashMap::new(),
         }
     }
 
     /// Inserts an engine into the Quaero instance.
-    pub fn push_engine(&mut self, engine: TaggedEngine) {
-        self.engines.push(engine)
+    ///
+    /// The engines are shared with every clone of the quaero instance, and changing them
+    /// doesn't affect searches which are already in flight.
+    pub fn push_engine(&self, engine: TaggedEngine) {
+        self.extend_engines([engine]);
     }
 
     /// Extends the quaero instance's engines with an iterator.
-    pub fn extend_engines<I: IntoIterator<Item = TaggedEngine>>(&mut self, iter: I) {
-        self.engines.extend(iter);
+    pub fn extend_engines<I: IntoIterator<Item = TaggedEngine>>(&self, iter: I) {
+        let engines: Vec<TaggedEngine> = iter.into_iter().collect();
+
+        self.engines.rcu(|set| {
+            let mut set = EngineSet::clone(set);
+            set.engines.extend(engines.iter().cloned());
+            set
+        });
+    }
+
+    /// Atomically replaces every engine. Engines which are kept (by identifier)
+    /// keep whether they are disabled along with their timeout.
+    pub fn replace_engines(&self, engines: impl Into<SmallVec<[TaggedEngine; N]>>) {
+        let engines = engines.into();
+        self.engines.rcu(|set| set.with_engines(engines.clone()));
+    }
+
+    /// Removes the selected engines (by identifier or name), returning how many were removed.
+    pub fn remove_engines(&self, selector: impl Into<EngineSelector>) -> usize {
+        let selector = selector.into();
+        let mut removed = 0;
+
+        self.engines.rcu(|set| {
+            let engines: SmallVec<[TaggedEngine; N]> = set
+                .engines
+                .iter()
+                .filter(|TaggedEngine(engine_id, ..)| !selector.matches(engine_id))
+                .cloned()
+                .collect();
+
+            removed = set.engines.len() - engines.len();
+            set.with_engines(engines)
+        });
+
+        removed
+    }
+
+    /// Enables or disables the selected engines (by identifier or name),
+    /// returning how many engines were selected. Disabled engines are skipped by every search
+    /// until they are enabled again.
+    pub fn set_engines_enabled(&self, selector: impl Into<EngineSelector>, enabled: bool) -> usize {
+        let selector = selector.into();
+        let mut selected = 0;
+
+        self.engines.rcu(|set| {
+            let engine_ids = set.select(&selector);
+            selected = engine_ids.len();
+
+            let mut set = EngineSet::clone(set);
+            for engine_id in engine_ids {
+                match enabled {
+                    true => set.disabled.remove(&engine_id),
+                    false => set.disabled.insert(engine_id),
+                };
+            }
+            set
+        });
+
+        selected
+    }
+
+    /// Sets the maximum amount of time a specific engine has to fetch its results,
+    /// overriding the default timeout. Like the engines themselves, this applies to every clone.
+    pub fn set_engine_timeout(&self, engine_id: &EngineId, timeout: Duration) {
+        self.engines.rcu(|set| {
+            let mut set = EngineSet::clone(set);
+            set.timeouts.insert(engine_id.clone(), timeout);
+            set
+        });
+    }
+
+    /// Returns information about each engine, in the order they were added.
+    pub fn engines(&self) -> Vec<EngineInfo> {
+        let set = self.engines.load();
+
+        set.engines
+            .iter()
+            .map(|engine| self.engine_info(&set, engine))
+            .collect()
+    }
+
+    /// Returns information about the selected engine (by identifier or name).
+    /// If several engines share the name, the first one which was added is returned.
+    pub fn engine(&self, selector: impl Into<EngineSelector>) -> Option<EngineInfo> {
+        let selector = selector.into();
+        let set = self.engines.load();
+
+        set.engines
+            .iter()
+            .find(|TaggedEngine(engine_id, ..)| selector.matches(engine_id))
+            .map(|engine| self.engine_info(&set, engine))
+    }
+
+    fn engine_info(&self, set: &EngineSet<N>, engine: &TaggedEngine) -> EngineInfo {
+        let TaggedEngine(engine_id, engine, weight) = engine;
+
+        EngineInfo {
+            engine_id: engine_id.clone(),
+            name: engine.name(),
+            homepage: engine.homepage(),
+            categories: engine.categories(),
+            weight: weight.clone(),
+            enabled: !set.disabled.contains(engine_id),
+            timeout: set.timeout_for(engine_id, self.timeout),
+            health: self.health.health(engine_id),
+        }
+    }
+
+    /// Returns the weight of the specified engine, which can be adjusted at runtime.
+    pub fn engine_weight(&self, engine_id: &EngineId) -> Option<EngineWeight> {
+        self.engines
+            .load()
+            .get(engine_id)
+            .map(|TaggedEngine(_, _, weight)| weight.clone())
+    }
+
+    /// Returns the health of each engine, including whether it is temporarily disabled.
+    pub fn engine_health(&self) -> Vec<(EngineId, EngineHealth)> {
+        self.engines
+            .load()
+            .engines
+            .iter()
+            .map(|TaggedEngine(engine_id, ..)| (engine_id.clone(), self.health.health(engine_id)))
+            .collect()
+    }
+
+    /// Sets when engines are temporarily disabled after repeatedly being blocked or
+    /// timing out, and for how long. This resets the health of every engine.
+    pub fn circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+        self.health = Arc::new(HealthTracker::new(circuit_breaker));
+        self
+    }
+
+    /// Limits how often a specific engine is queried. The limit is shared across every
+    /// search made by this quaero instance (and its clones), with each page, retry and hedged
+    /// request counting as a request.
+    pub fn engine_rate_limit(mut self, engine_id: &EngineId, rate_limit: RateLimit) -> Self {
+        self.rate_limiter = Arc::new(self.rate_limiter.with_limit(engine_id, rate_limit));
+        self
+    }
+
+    /// Sets what happens when an engine is queried while it's over its rate limit.
+    pub fn over_budget(mut self, over_budget: OverBudget) -> Self {
+        self.rate_limiter = Arc::new(self.rate_limiter.with_over_budget(over_budget));
+        self
+    }
+
+    /// Sets the default policy for retrying and hedging requests to each engine.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Arc::new(retry_policy);
+        self
+    }
+
+    /// Sets the policy for retrying and hedging requests to a specific engine,
+    /// overriding the default policy.
+    pub fn engine_retry_policy(mut self, engine_id: &EngineId, retry_policy: RetryPolicy) -> Self {
+        self.engine_retry_policies
+            .insert(engine_id.clone(), Arc::new(retry_policy));
+        self
+    }
+
+    /// Sets the pool of clients which engines send their requests through,
+    /// unless they have their own pool. The pool is shared between these engines,
+    /// so it rotates whenever any of them is blocked or shown a captcha.
+    pub fn client_pool(mut self, clients: ClientPool<C>) -> Self {
+        self.clients = Arc::new(clients);
+        self
+    }
+
+    /// Sets the pool of clients which a specific engine sends its requests through,
+    /// such as a pool of proxies. The pool rotates whenever the engine is blocked
+    /// or shown a captcha.
+    pub fn engine_clients(mut self, engine_id: &EngineId, clients: ClientPool<C>) -> Self {
+        self.engine_clients
+            .insert(engine_id.clone(), Arc::new(clients));
+        self
     }
 
-    /// Sets and initializes the score refiner for this quaero instance.
+    /// Appends middleware which intercepts the requests made to every engine.
+    pub fn middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Appends middleware which only intercepts the requests made to a specific engine.
+    /// It runs inside of any global middleware.
+    pub fn engine_middleware(
+        mut self,
+        engine_id: &EngineId,
+        middleware: impl Middleware + 'static,
+    ) -> Self {
+        self.engine_middleware
+            .entry(engine_id.clone())
+            .or_default()
+            .push(Arc::new(middleware));
+        self
+    }
+
+    /// Initializes a score refiner and appends it to this quaero instance's refiner pipeline.
     ///
     /// Score refiners apply another stage of reranking the the search results.
+    /// Stages run in the order they were added, each reranking the top
+    /// [ScoreRefiner::max_results] results of the stage before it.
     pub async fn score_refiner(mut self, refiner: impl ScoreRefiner + 'static) -> Self {
         let _ = refiner.init().await;
 
-        self.score_refiner = Some(Box::new(refiner));
+        self.score_refiners.push(Arc::new(refiner));
+        self
+    }
+
+    /// Sets how each score refiner's scores are blended with the scores of the stage before it.
+    pub fn refiner_blend(mut self, blend: RefinerBlend) -> Self {
+        self.refiner_blend = blend;
+        self
+    }
+
+    /// Sets how long the score refiners have to refine the results of a search, in total.
+    /// Any stages which don't finish in time are skipped, leaving the results in the order
+    /// of the last stage which did.
+    pub fn refine_budget(mut self, budget: Duration) -> Self {
+        self.refine_budget = Some(budget);
+        self
+    }
+
+    /// Sets the ranker which calculates the lexical score of each search result
+    /// once every engine has reported. Defaults to [Bm25].
+    pub fn ranker(mut self, ranker: impl Ranker + 'static) -> Self {
+        self.ranker = Arc::new(ranker);
+        self
+    }
+
+    /// Sets how the rank each engine gave a search result is combined with its lexical score.
+    pub fn rank_fusion(mut self, rank_fusion: RankFusion) -> Self {
+        self.rank_fusion = rank_fusion;
+        self
+    }
+
+    /// Sets how search results which are near-duplicates of each other are detected and collapsed.
+    pub fn near_duplicates(mut self, near_duplicates: NearDuplicates) -> Self {
+        self.near_duplicates = near_duplicates;
         self
     }
 
@@ -92,13 +428,161 @@ impl<C: HttpClient + Send + Sync + 'static, const N: usize> Quaero<C, N> {
         self
     }
 
+    /// Sets the cache used to store search responses so repeated searches don't hit the engines.
+    pub fn cache(mut self, cache: impl SearchCache + 'static) -> Self {
+        self.cache = Some(Arc::new(cache));
+        self
+    }
+
+    /// Sets where metrics about engine latencies, engine errors and cache lookups are recorded.
+    ///
+    /// The metrics are shared, so a handle can be kept to read or export them.
+    pub fn metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
     /// Performs a search query across all of the quaero instance's engines and aggregates their results.
     pub async fn search<'a>(
         &'a self,
         query: impl AsRef<str>,
         options: impl Into<Arc<SearchOptions>>,
     ) -> SearchResponse {
-        aggregate_results(self, query.as_ref(), options.into()).await
+        let (query, options) = (query.as_ref(), options.into());
+
+        let Some(cache) = &self.cache else {
+            return aggregate_results(self, query, options, &SearchPlan::default()).await;
+        };
+
+        let key = cache_key(query, &options);
+
+        if let Some(response) = self
+            .cached_response(cache.as_ref(), &key, query, &options)
+            .await
+        {
+            return response;
+        }
+
+        let response = aggregate_results(self, query, options, &SearchPlan::default()).await;
+        cache::store(cache.as_ref(), &key, &response).await;
+
+        response
+    }
+
+    /// Performs a search query across all of the quaero instance's engines,
+    /// yielding events as each engine finishes instead of waiting for all of them.
+    ///
+    /// The last event is always [SearchEvent::Finished], which contains the same
+    /// response that [Quaero::search] would have returned.
+    pub fn search_stream<'a>(
+        &'a self,
+        query: impl AsRef<str>,
+        options: impl Into<Arc<SearchOptions>>,
+    ) -> impl Stream<Item = SearchEvent> + Send + 'a {
+        aggregate_results_stream(self, query.as_ref().to_string(), options.into())
+    }
+
+    /// Starts a session for paging through a search query without repeating results.
+    ///
+    /// See [SearchSession::next_page].
+    pub fn session(
+        &self,
+        query: impl AsRef<str>,
+        options: impl Into<Arc<SearchOptions>>,
+    ) -> SearchSession {
+        SearchSession::new(query, options)
+    }
+}
+
+impl<C: HttpClient + Send + Sync + 'static, const N: usize> Quaero<C, N> {
+    /// Looks up a cached response, refreshing it in the background if it is stale.
+    pub(crate) async fn cached_response(
+        &self,
+        cache: &dyn SearchCache,
+        key: &str,
+        query: &str,
+        options: &Arc<SearchOptions>,
+    ) -> Option<SearchResponse> {
+        let cached = cache.get(key).await;
+
+        if let Some(metrics) = &self.metrics {
+            metrics.cache_lookup(
+                cached
+                    .as_ref()
+                    .map_or(CacheStatus::Miss, |(_, status)| *status),
+            );
+        }
+
+        let (mut response, cache_status) = cached?;
+
+        if cache_status == CacheStatus::Stale {
+            self.revalidate(key, query, options.clone());
+        }
+
+        response.cache_status = cache_status;
+        Some(response)
+    }
+
+    /// Returns the pool of clients the specified engine sends its requests through.
+    pub(crate) fn clients_for(&self, engine_id: &EngineId) -> Arc<ClientPool<C>> {
+        self.engine_clients
+            .get(engine_id)
+            .unwrap_or(&self.clients)
+            .clone()
+    }
+
+    /// Returns the global middleware followed by the middleware for the specified engine,
+    /// with the middleware applying the engine's configured base url and headers last.
+    pub(crate) fn middleware_for(
+        &self,
+        engines: &EngineSet<N>,
+        engine_id: &EngineId,
+    ) -> Arc<[Arc<dyn Middleware>]> {
+        self.middleware
+            .iter()
+            .chain(self.engine_middleware.get(engine_id).into_iter().flatten())
+            .chain(engines.overrides.get(engine_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the retry policy for the specified engine.
+    pub(crate) fn retry_policy_for(&self, engine_id: &EngineId) -> Arc<RetryPolicy> {
+        self.engine_retry_policies
+            .get(engine_id)
+            .unwrap_or(&self.retry_policy)
+            .clone()
+    }
+
+    /// Fetches a fresh response for a stale cache entry in the background.
+    fn revalidate(&self, key: &str, query: &str, options: Arc<SearchOptions>) {
+        let Some(cache) = self.cache.clone() else {
+            return;
+        };
+
+        let is_already_revalidating = !self
+            .revalidating
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .insert(key.to_string());
+
+        if is_already_revalidating {
+            return;
+        }
+
+        let (quaero, key, query) = (self.clone(), key.to_string(), query.to_string());
+
+        tokio::spawn(async move {
+            let response =
+                aggregate_results(&quaero, &query, options, &SearchPlan::default()).await;
+            cache::store(cache.as_ref(), &key, &response).await;
+
+            quaero
+                .revalidating
+                .lock()
+                .unwrap_or_else(|err| err.into_inner())
+                .remove(&key);
+        });
     }
 }
 
@@ -108,10 +592,25 @@ impl<C: HttpClient + Send + Sync + 'static> Quaero<C> {
         init();
 
         Quaero {
-            client: client.into(),
-            engines: SmallVec::new(),
-            score_refiner: None,
+            clients: Arc::new(ClientPool::new([client.into()])),
+            engine_clients: HashMap::new(),
+            engines: Arc::new(ArcSwap::from_pointee(EngineSet::new(SmallVec::new()))),
+            score_refiners: Vec::new(),
+            refiner_blend: RefinerBlend::default(),
+            refine_budget: None,
+            ranker: Arc::new(Bm25::default()),
+            rank_fusion: RankFusion::default(),
+            near_duplicates: NearDuplicates::default(),
             timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            cache: None,
+            revalidating: Arc::default(),
+            health: Arc::default(),
+            rate_limiter: Arc::default(),
+            metrics: None,
+            retry_policy: Arc::default(),
+            engine_retry_policies: HashMap::new(),
+            middleware: Vec::new(),
+            engine_middleware: HashMap::new(),
         }
     }
 }
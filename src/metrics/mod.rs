@@ -0,0 +1,33 @@
+//! A facade for recording metrics about engines and caching, along with its implementations.
+
+use std::time::Duration;
+
+use quaero_shared::models::{
+    engine::EngineId,
+    search::{CacheStatus, SearchError},
+};
+
+#[cfg(feature = "prometheus")]
+mod prometheus;
+#[cfg(feature = "prometheus")]
+pub use prometheus::*;
+
+/// A trait for recording metrics as searches are made. Every method does nothing by default,
+/// so implementations only need to override the metrics they are interested in.
+pub trait Metrics: Send + Sync {
+    /// Records how long an engine took to return its results (including any retries),
+    /// whether it succeeded or not.
+    fn engine_latency(&self, engine_id: &EngineId, engine_name: &str, latency: Duration) {
+        let _ = (engine_id, engine_name, latency);
+    }
+
+    /// Records an engine failing to return results.
+    fn engine_error(&self, engine_id: &EngineId, engine_name: &str, error: &SearchError) {
+        let _ = (engine_id, engine_name, error);
+    }
+
+    /// Records a search cache lookup.
+    fn cache_lookup(&self, status: CacheStatus) {
+        let _ = status;
+    }
+}
@@ -0,0 +1,204 @@
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex, time::Duration};
+
+use quaero_shared::models::{
+    engine::EngineId,
+    search::{CacheStatus, SearchError},
+};
+
+use crate::metrics::Metrics;
+
+/// The upper bounds (in seconds) of the engine latency histogram's buckets.
+const LATENCY_BUCKETS: [f64; 9] = [0.05, 0.1, 0.25, 0.5, 1., 2.5, 5., 10., 20.];
+
+/// Keeps metrics in memory so they can be exported in the Prometheus text format.
+#[derive(Default)]
+pub struct PrometheusMetrics {
+    inner: Mutex<PrometheusMetricsInner>,
+}
+
+#[derive(Default)]
+struct PrometheusMetricsInner {
+    engine_latencies: BTreeMap<String, LatencyHistogram>,
+    engine_errors: BTreeMap<(String, &'static str), u64>,
+    cache_lookups: BTreeMap<&'static str, u64>,
+}
+
+#[derive(Default)]
+struct LatencyHistogram {
+    bucket_counts: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl PrometheusMetrics {
+    /// Creates a new [PrometheusMetrics] with no recorded metrics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Renders every recorded metric in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let inner = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        let mut output = String::new();
+
+        let _ = writeln!(
+            output,
+            "# HELP quaero_engine_latency_seconds How long each engine took to return its results."
+        );
+        let _ = writeln!(output, "# TYPE quaero_engine_latency_seconds histogram");
+
+        for (engine, histogram) in &inner.engine_latencies {
+            let engine = escape_label(engine);
+
+            for (upper_bound, count) in LATENCY_BUCKETS.iter().zip(histogram.bucket_counts) {
+                let _ = writeln!(
+                    output,
+                    "quaero_engine_latency_seconds_bucket{{engine=\"{engine}\",le=\"{upper_bound}\"}} {count}"
+                );
+            }
+
+            let _ = writeln!(
+                output,
+                "quaero_engine_latency_seconds_bucket{{engine=\"{engine}\",le=\"+Inf\"}} {}",
+                histogram.count
+            );
+            let _ = writeln!(
+                output,
+                "quaero_engine_latency_seconds_sum{{engine=\"{engine}\"}} {}",
+                histogram.sum
+            );
+            let _ = writeln!(
+                output,
+                "quaero_engine_latency_seconds_count{{engine=\"{engine}\"}} {}",
+                histogram.count
+            );
+        }
+
+        let _ = writeln!(
+            output,
+            "# HELP quaero_engine_errors_total How many times each engine failed, by kind of error."
+        );
+        let _ = writeln!(output, "# TYPE quaero_engine_errors_total counter");
+
+        for ((engine, kind), count) in &inner.engine_errors {
+            let _ = writeln!(
+                output,
+                "quaero_engine_errors_total{{engine=\"{}\",kind=\"{kind}\"}} {count}",
+                escape_label(engine)
+            );
+        }
+
+        let _ = writeln!(
+            output,
+            "# HELP quaero_cache_lookups_total How many search cache lookups there were, by status."
+        );
+        let _ = writeln!(output, "# TYPE quaero_cache_lookups_total counter");
+
+        for (status, count) in &inner.cache_lookups {
+            let _ = writeln!(
+                output,
+                "quaero_cache_lookups_total{{status=\"{status}\"}} {count}"
+            );
+        }
+
+        output
+    }
+}
+
+impl Metrics for PrometheusMetrics {
+    fn engine_latency(&self, _engine_id: &EngineId, engine_name: &str, latency: Duration) {
+        let mut inner = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        let histogram = inner
+            .engine_latencies
+            .entry(engine_name.to_string())
+            .or_default();
+
+        let secs = latency.as_secs_f64();
+
+        for (upper_bound, count) in LATENCY_BUCKETS.iter().zip(&mut histogram.bucket_counts) {
+            if secs <= *upper_bound {
+                *count += 1;
+            }
+        }
+
+        histogram.count += 1;
+        histogram.sum += secs;
+    }
+
+    fn engine_error(&self, _engine_id: &EngineId, engine_name: &str, error: &SearchError) {
+        let mut inner = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+
+        *inner
+            .engine_errors
+            .entry((engine_name.to_string(), error.kind()))
+            .or_default() += 1;
+    }
+
+    fn cache_lookup(&self, status: CacheStatus) {
+        let status = match status {
+            CacheStatus::Miss => "miss",
+            CacheStatus::Hit => "hit",
+            CacheStatus::Stale => "stale",
+        };
+
+        let mut inner = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        *inner.cache_lookups.entry(status).or_default() += 1;
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render() {
+        let metrics = PrometheusMetrics::new();
+        let engine_id: EngineId = "Google:V1StGXR8_Z".parse().unwrap();
+
+        metrics.engine_latency(&engine_id, "Google", Duration::from_millis(300));
+        metrics.engine_latency(&engine_id, "Google", Duration::from_secs(30));
+        metrics.engine_error(&engine_id, "Google", &SearchError::Timeout);
+        metrics.engine_error(&engine_id, "Google", &SearchError::Timeout);
+        metrics.engine_error(
+            &engine_id,
+            "My \"Search\"\\\nEngine",
+            &SearchError::RateLimited,
+        );
+        metrics.cache_lookup(CacheStatus::Hit);
+        metrics.cache_lookup(CacheStatus::Miss);
+        metrics.cache_lookup(CacheStatus::Hit);
+
+        let expected = r#"# HELP quaero_engine_latency_seconds How long each engine took to return its results.
+# TYPE quaero_engine_latency_seconds histogram
+quaero_engine_latency_seconds_bucket{engine="Google",le="0.05"} 0
+quaero_engine_latency_seconds_bucket{engine="Google",le="0.1"} 0
+quaero_engine_latency_seconds_bucket{engine="Google",le="0.25"} 0
+quaero_engine_latency_seconds_bucket{engine="Google",le="0.5"} 1
+quaero_engine_latency_seconds_bucket{engine="Google",le="1"} 1
+quaero_engine_latency_seconds_bucket{engine="Google",le="2.5"} 1
+quaero_engine_latency_seconds_bucket{engine="Google",le="5"} 1
+quaero_engine_latency_seconds_bucket{engine="Google",le="10"} 1
+quaero_engine_latency_seconds_bucket{engine="Google",le="20"} 1
+quaero_engine_latency_seconds_bucket{engine="Google",le="+Inf"} 2
+quaero_engine_latency_seconds_sum{engine="Google"} 30.3
+quaero_engine_latency_seconds_count{engine="Google"} 2
+# HELP quaero_engine_errors_total How many times each engine failed, by kind of error.
+# TYPE quaero_engine_errors_total counter
+quaero_engine_errors_total{engine="Google",kind="timeout"} 2
+quaero_engine_errors_total{engine="My \"Search\"\\\nEngine",kind="rate_limited"} 1
+# HELP quaero_cache_lookups_total How many search cache lookups there were, by status.
+# TYPE quaero_cache_lookups_total counter
+quaero_cache_lookups_total{status="hit"} 2
+quaero_cache_lookups_total{status="miss"} 1
+"#;
+
+        assert_eq!(metrics.render(), expected);
+    }
+}
@@ -0,0 +1,39 @@
+//! Middleware which can inspect and modify the requests sent to engines and the responses they return.
+
+use anyhttp::Response;
+use http::Request;
+
+use quaero_shared::models::{engine::EngineId, search::SearchError};
+
+/// A trait for intercepting the http requests made to engines, such as to add auth headers,
+/// rewrite hosts, log traffic or inject faults.
+///
+/// Middleware composes like layers: requests pass through each middleware in the order
+/// it was added, while responses pass back through them in reverse order.
+/// Global middleware wraps any middleware specific to an engine.
+#[async_trait::async_trait]
+pub trait Middleware: Send + Sync {
+    /// Inspects or modifies a request before it is sent.
+    /// Returning an error fails the request without sending it.
+    async fn on_request(
+        &self,
+        engine_id: &EngineId,
+        engine_name: &str,
+        request: &mut Request<Vec<u8>>,
+    ) -> Result<(), SearchError> {
+        let _ = (engine_id, engine_name, request);
+        Ok(())
+    }
+
+    /// Inspects a response before it is validated and parsed, returning it
+    /// (or a replacement for it). Returning an error fails the request.
+    async fn on_response(
+        &self,
+        engine_id: &EngineId,
+        engine_name: &str,
+        response: Response,
+    ) -> Result<Response, SearchError> {
+        let _ = (engine_id, engine_name);
+        Ok(response)
+    }
+}
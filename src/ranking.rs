@@ -0,0 +1,286 @@
+//! Rankers which score how relevant each search result's contents are to the query,
+//! once every engine has reported.
+
+use quaero_shared::models::{
+    ranker::Ranker,
+    search::{SearchResultWithMetadata, TokenContribution},
+};
+use rayon::prelude::*;
+
+/// The fields of a search result which are scored, in the order their weights are stored.
+const FIELD_COUNT: usize = 3;
+
+/// Scores search results with BM25F, using the merged results themselves as the corpus.
+/// This is the default [Ranker].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bm25 {
+    /// How quickly repeated occurrences of a term stop adding to the score.
+    pub k1: f32,
+
+    /// How much a field's length relative to the average length of that field
+    /// dampens its term frequencies (between `0.0` and `1.0`).
+    pub b: f32,
+
+    /// How much an occurrence in the title counts towards a term's frequency.
+    pub title_weight: f32,
+
+    /// How much an occurrence in the url counts towards a term's frequency.
+    pub url_weight: f32,
+
+    /// How much an occurrence in the summary counts towards a term's frequency.
+    pub summary_weight: f32,
+}
+
+impl Default for Bm25 {
+    fn default() -> Self {
+        Self {
+            k1: 1.2,
+            b: 0.75,
+            title_weight: 2.,
+            url_weight: 0.5,
+            summary_weight: 1.,
+        }
+    }
+}
+
+impl Bm25 {
+    fn field_weights(&self) -> [f32; FIELD_COUNT] {
+        [self.title_weight, self.url_weight, self.summary_weight]
+    }
+}
+
+/// The length of each field of a search result and how often each query term occurs in them.
+struct FieldStats {
+    lengths: [f32; FIELD_COUNT],
+    term_frequencies: Box<[[f32; FIELD_COUNT]]>,
+}
+
+/// How much each unique query term contributes to each search result's score.
+struct TermScores<'a> {
+    terms: Vec<&'a str>,
+    /// One row per search result, with one score per term.
+    scores: Box<[Box<[f32]>]>,
+}
+
+impl FieldStats {
+    fn new(result: &SearchResultWithMetadata, query_terms: &[&str]) -> Self {
+        let fields = [
+            &result.search_result.title,
+            &result.search_result.url,
+            &result.search_result.summary,
+        ];
+
+        let mut lengths = [0.; FIELD_COUNT];
+        let mut term_frequencies = vec![[0.; FIELD_COUNT]; query_terms.len()].into_boxed_slice();
+
+        for (field_idx, field) in fields.into_iter().enumerate() {
+            for token in tokenize(field) {
+                lengths[field_idx] += 1.;
+
+                if let Some(term_idx) = query_terms.iter().position(|term| *term == token) {
+                    term_frequencies[term_idx][field_idx] += 1.;
+                }
+            }
+        }
+
+        Self {
+            lengths,
+            term_frequencies,
+        }
+    }
+}
+
+impl Bm25 {
+    /// Scores how much each unique query term contributes to each search result's score.
+    fn term_scores<'a>(
+        &self,
+        query_tokens: &'a [String],
+        results: &[SearchResultWithMetadata],
+    ) -> TermScores<'a> {
+        let mut query_terms: Vec<&str> = query_tokens.iter().map(String::as_str).collect();
+        query_terms.sort_unstable();
+        query_terms.dedup();
+
+        if query_terms.is_empty() {
+            return TermScores {
+                terms: query_terms,
+                scores: vec![Box::default(); results.len()].into_boxed_slice(),
+            };
+        }
+
+        let stats: Box<[FieldStats]> = results
+            .par_iter()
+            .map(|result| FieldStats::new(result, &query_terms))
+            .collect();
+
+        let result_count = results.len() as f32;
+
+        let mut average_lengths = [0.; FIELD_COUNT];
+        for stats in &stats {
+            for (average_length, length) in average_lengths.iter_mut().zip(stats.lengths) {
+                *average_length += length / result_count;
+            }
+        }
+
+        let inverse_document_frequencies: Box<[f32]> = (0..query_terms.len())
+            .map(|term_idx| {
+                let document_frequency = stats
+                    .iter()
+                    .filter(|stats| stats.term_frequencies[term_idx].iter().any(|tf| *tf > 0.))
+                    .count() as f32;
+
+                (1. + (result_count - document_frequency + 0.5) / (document_frequency + 0.5)).ln()
+            })
+            .collect();
+
+        let field_weights = self.field_weights();
+
+        let scores = stats
+            .par_iter()
+            .map(|stats| {
+                stats
+                    .term_frequencies
+                    .iter()
+                    .zip(&inverse_document_frequencies)
+                    .map(|(term_frequencies, idf)| {
+                        let term_frequency: f32 = (0..FIELD_COUNT)
+                            .map(|field_idx| {
+                                let average_length = average_lengths[field_idx];
+                                let length_ratio = match average_length > 0. {
+                                    true => stats.lengths[field_idx] / average_length,
+                                    false => 1.,
+                                };
+
+                                field_weights[field_idx] * term_frequencies[field_idx]
+                                    / (1. - self.b + self.b * length_ratio)
+                            })
+                            .sum();
+
+                        idf * term_frequency / (self.k1 + term_frequency)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        TermScores {
+            terms: query_terms,
+            scores,
+        }
+    }
+}
+
+impl Ranker for Bm25 {
+    fn scores(
+        &self,
+        _query: &str,
+        query_tokens: &[String],
+        results: &[SearchResultWithMetadata],
+    ) -> Box<[f32]> {
+        self.term_scores(query_tokens, results)
+            .scores
+            .iter()
+            .map(|term_scores| term_scores.iter().sum())
+            .collect()
+    }
+
+    fn token_scores(
+        &self,
+        _query: &str,
+        query_tokens: &[String],
+        results: &[SearchResultWithMetadata],
+    ) -> Option<Box<[Vec<TokenContribution>]>> {
+        let TermScores { terms, scores } = self.term_scores(query_tokens, results);
+
+        let token_scores = scores
+            .iter()
+            .map(|term_scores| {
+                terms
+                    .iter()
+                    .zip(term_scores)
+                    .filter(|(_, score)| **score > 0.)
+                    .map(|(term, score)| TokenContribution {
+                        token: term.to_string(),
+                        synonym_of: None,
+                        score: *score,
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Some(token_scores)
+    }
+}
+
+/// Splits text into lowercase words, the same way queries are split into tokens.
+/// Stop words aren't removed from fields as they never match a query token,
+/// although they still count towards the field's length.
+///
+/// Chinese and Japanese aren't written with spaces, so runs of Han, Hiragana or Katakana
+/// are split into their characters and each pair of adjacent characters instead.
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|char: char| !char.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .flat_map(segment_word)
+}
+
+/// The scripts which are written without spaces between words.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Han,
+    Hiragana,
+    Katakana,
+}
+
+impl Script {
+    fn of(char: char) -> Option<Self> {
+        match char {
+            '\u{3005}'
+            | '\u{3007}'
+            | '\u{3400}'..='\u{4DBF}'
+            | '\u{4E00}'..='\u{9FFF}'
+            | '\u{F900}'..='\u{FAFF}'
+            | '\u{20000}'..='\u{2FA1F}' => Some(Self::Han),
+            '\u{3041}'..='\u{309F}' => Some(Self::Hiragana),
+            '\u{30A0}'..='\u{30FF}' | '\u{31F0}'..='\u{31FF}' | '\u{FF66}'..='\u{FF9F}' => {
+                Some(Self::Katakana)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Splits a word into runs of the same script, segmenting the runs which aren't
+/// written with spaces into characters and character bigrams.
+fn segment_word(word: &str) -> Vec<String> {
+    if !word.chars().any(|char| Script::of(char).is_some()) {
+        return vec![word.to_lowercase()];
+    }
+
+    let mut tokens = Vec::new();
+    let mut run_start = 0;
+    let mut run_script = None;
+
+    for (idx, char) in word.char_indices().chain([(word.len(), ' ')]) {
+        let script = Script::of(char);
+
+        if idx == word.len() || (idx > run_start && script != run_script) {
+            let run = &word[run_start..idx];
+
+            match run_script {
+                Some(_) => {
+                    let chars: Vec<char> = run.chars().collect();
+
+                    tokens.extend(chars.iter().map(char::to_string));
+                    tokens.extend(chars.windows(2).map(|pair| pair.iter().collect()));
+                }
+                None => tokens.push(run.to_lowercase()),
+            }
+
+            run_start = idx;
+        }
+
+        run_script = script;
+    }
+
+    tokens
+}
@@ -0,0 +1,280 @@
+//! Limits how often each engine is queried, shared across every search made by a quaero instance.
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tracing::info;
+
+use quaero_shared::models::{
+    engine::EngineId,
+    search::{SearchResponse, SearchResult, SearchResultWithMetadata},
+    search_cache::SearchCache,
+};
+
+/// A token bucket limit on how many requests can be made to an engine.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// How many requests the bucket refills by each second.
+    pub per_second: f64,
+
+    /// How many requests can be made in a burst before the refill rate applies.
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Allows the specified amount of requests per second, with bursts of up to that amount.
+    pub fn per_second(requests: u32) -> Self {
+        Self {
+            per_second: requests as f64,
+            burst: requests.max(1),
+        }
+    }
+
+    /// Allows the specified amount of requests per minute, with bursts of up to that amount.
+    pub fn per_minute(requests: u32) -> Self {
+        Self {
+            per_second: requests as f64 / 60.,
+            burst: requests.max(1),
+        }
+    }
+
+    /// Sets how many requests can be made in a burst.
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+}
+
+/// What happens when an engine is queried while it's over its rate limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverBudget {
+    /// Waits for the engine to be under its rate limit again, for up to the specified
+    /// duration (or the search's timeout, whichever is shorter).
+    Queue {
+        /// The longest to wait before the engine is skipped.
+        max_wait: Duration,
+    },
+
+    /// Skips the engine, reporting [SearchError::RateLimited](quaero_shared::models::search::SearchError::RateLimited) as its status.
+    Skip,
+
+    /// Uses the results the engine last returned for the same search, as long as they
+    /// are still in the search cache. Otherwise the engine is skipped.
+    ServeFromCache,
+}
+
+impl Default for OverBudget {
+    fn default() -> Self {
+        Self::Queue {
+            max_wait: Duration::from_secs(2),
+        }
+    }
+}
+
+/// The rate limits of each engine along with how many requests they have left.
+#[derive(Default)]
+pub(crate) struct RateLimiter {
+    limits: HashMap<EngineId, RateLimit>,
+    buckets: Mutex<HashMap<EngineId, Bucket>>,
+    pub(crate) over_budget: OverBudget,
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    /// Creates a copy of this limiter's configuration with a different rate limit for an engine.
+    /// As it's used while building a quaero instance, the remaining budgets aren't copied.
+    pub(crate) fn with_limit(&self, engine_id: &EngineId, limit: RateLimit) -> Self {
+        let mut limits = self.limits.clone();
+        limits.insert(engine_id.clone(), limit);
+
+        Self {
+            limits,
+            buckets: Mutex::default(),
+            over_budget: self.over_budget,
+        }
+    }
+
+    /// Creates a copy of this limiter's configuration with a different over budget behaviour.
+    pub(crate) fn with_over_budget(&self, over_budget: OverBudget) -> Self {
+        Self {
+            limits: self.limits.clone(),
+            buckets: Mutex::default(),
+            over_budget,
+        }
+    }
+
+    /// Returns whether the engine has a rate limit.
+    pub(crate) fn is_limited(&self, engine_id: &EngineId) -> bool {
+        self.limits.contains_key(engine_id)
+    }
+
+    /// Takes a single request from the engine's budget, waiting for it if configured
+    /// to. Returns whether the request is allowed to be made.
+    pub(crate) async fn acquire(&self, engine_id: &EngineId, deadline: Instant) -> bool {
+        let Some(limit) = self.limits.get(engine_id) else {
+            return true;
+        };
+
+        let wait_deadline = match self.over_budget {
+            OverBudget::Queue { max_wait } => Instant::now()
+                .checked_add(max_wait)
+                .map_or(deadline, |max_wait_deadline| {
+                    deadline.min(max_wait_deadline)
+                }),
+            OverBudget::Skip | OverBudget::ServeFromCache => Instant::now(),
+        };
+
+        loop {
+            let wait = match self.try_acquire(engine_id, limit) {
+                Ok(()) => return true,
+                Err(wait) => wait,
+            };
+
+            if Instant::now()
+                .checked_add(wait)
+                .is_none_or(|ready_at| ready_at > wait_deadline)
+            {
+                return false;
+            }
+
+            info!("Waiting {wait:?} for an engine to be under its rate limit.");
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Takes a request from the engine's bucket, or returns how long until there is one.
+    fn try_acquire(&self, engine_id: &EngineId, limit: &RateLimit) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|err| err.into_inner());
+        let now = Instant::now();
+
+        let bucket = buckets.entry(engine_id.clone()).or_insert_with(|| Bucket {
+            tokens: limit.burst as f64,
+            refilled_at: now,
+        });
+
+        let refill = now.duration_since(bucket.refilled_at).as_secs_f64() * limit.per_second;
+        // Negative or NaN refill rates leave the bucket as is.
+        if refill > 0. {
+            bucket.tokens = (bucket.tokens + refill).min(limit.burst as f64);
+        }
+        bucket.refilled_at = now;
+
+        if bucket.tokens >= 1. {
+            bucket.tokens -= 1.;
+            return Ok(());
+        }
+
+        if limit.per_second.is_nan() || limit.per_second <= 0. {
+            return Err(Duration::MAX);
+        }
+
+        // Tiny (or non-finite) refill rates would take longer than a duration can hold.
+        Err(
+            Duration::try_from_secs_f64((1. - bucket.tokens) / limit.per_second)
+                .unwrap_or(Duration::MAX),
+        )
+    }
+}
+
+/// Returns the cache key an engine's own results are stored under.
+/// Keyed by id so engines which share a name don't serve each other's results.
+pub(crate) fn engine_cache_key(search_key: &str, engine_id: &EngineId) -> String {
+    format!("{search_key}\u{1f}engine={engine_id}")
+}
+
+/// Looks up the results an engine last returned for a search.
+pub(crate) async fn cached_engine_results(
+    cache: &dyn SearchCache,
+    key: &str,
+) -> Option<Vec<(usize, String, SearchResult)>> {
+    let (response, _) = cache.get(key).await?;
+
+    let mut results: Vec<_> = response
+        .results
+        .into_vec()
+        .into_iter()
+        .filter_map(|result| {
+            let (_, rank) = *result.ranks.first()?;
+            Some((rank, result.url_key, result.search_result))
+        })
+        .collect();
+    results.sort_by_key(|(rank, ..)| *rank);
+
+    Some(results)
+}
+
+/// Stores an engine's results so they can be used while it's over its rate limit.
+pub(crate) async fn store_engine_results(
+    cache: &dyn SearchCache,
+    key: &str,
+    engine_id: &EngineId,
+    results: &[(usize, String, SearchResult)],
+) {
+    let response = SearchResponse {
+        results: results
+            .iter()
+            .map(|(rank, url, result)| {
+                SearchResultWithMetadata::new(url.clone(), result.clone(), engine_id, *rank)
+            })
+            .collect(),
+        statuses: vec![(engine_id.clone(), Ok(()))],
+        cache_status: Default::default(),
+    };
+
+    cache.insert(key, &response).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine_id() -> EngineId {
+        "Google:V1StGXR8_Z".parse().unwrap()
+    }
+
+    #[test]
+    fn takes_a_token_per_request() {
+        let limit = RateLimit::per_minute(1).burst(3);
+        let rate_limiter = RateLimiter::default().with_limit(&engine_id(), limit);
+
+        for _ in 0..3 {
+            assert_eq!(rate_limiter.try_acquire(&engine_id(), &limit), Ok(()));
+        }
+
+        let wait = rate_limiter.try_acquire(&engine_id(), &limit).unwrap_err();
+        assert!(wait > Duration::from_secs(50) && wait <= Duration::from_secs(60));
+    }
+
+    #[test]
+    fn unusable_refill_rates_never_refill() {
+        for per_second in [0., -1., f64::NAN, f64::MIN_POSITIVE] {
+            let limit = RateLimit {
+                per_second,
+                burst: 1,
+            };
+            let rate_limiter = RateLimiter::default().with_limit(&engine_id(), limit);
+
+            assert_eq!(rate_limiter.try_acquire(&engine_id(), &limit), Ok(()));
+            assert_eq!(
+                rate_limiter.try_acquire(&engine_id(), &limit),
+                Err(Duration::MAX)
+            );
+        }
+    }
+
+    #[test]
+    fn engines_with_the_same_name_have_their_own_cache_keys() {
+        let other_engine_id = "Google:ppAxUPbUbQ".parse().unwrap();
+
+        assert_ne!(
+            engine_cache_key("rust", &engine_id()),
+            engine_cache_key("rust", &other_engine_id)
+        );
+    }
+}
@@ -0,0 +1,456 @@
+//! Runs the score refiner pipeline, blending the scores of each stage
+//! with the scores of the stage before it.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use quaero_shared::models::{score_refiner::ScoreRefiner, search::SearchResultWithMetadata};
+use rayon::prelude::*;
+use tracing::{Instrument, info_span, warn};
+
+/// How the previous and refined scores are brought onto the same scale before being blended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScoreNormalization {
+    /// Rescales scores to be between `0.0` and `1.0`.
+    #[default]
+    MinMax,
+
+    /// Rescales scores by how many standard deviations they are from the mean.
+    ZScore,
+}
+
+/// Configures how the scores of each score refiner are combined with the scores of the stage before it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RefinerBlend {
+    /// How both sets of scores are normalised.
+    pub normalization: ScoreNormalization,
+
+    /// How much the refined score contributes to the final score (between `0.0` and `1.0`).
+    /// The remainder is contributed by the previous stage's score.
+    pub refined_weight: f32,
+}
+
+impl RefinerBlend {
+    /// Only uses the refined scores, although they are still rescaled
+    /// to be comparable with the results which weren't refined.
+    pub fn refined_only() -> Self {
+        Self {
+            normalization: ScoreNormalization::MinMax,
+            refined_weight: 1.,
+        }
+    }
+}
+
+impl Default for RefinerBlend {
+    fn default() -> Self {
+        Self {
+            normalization: ScoreNormalization::MinMax,
+            refined_weight: 0.7,
+        }
+    }
+}
+
+/// Runs each stage of the refiner pipeline in order. If a stage fails or the time budget
+/// runs out, the results are left in the order of the last stage which succeeded.
+pub(crate) async fn refine_results(
+    mut results: Box<[SearchResultWithMetadata]>,
+    query: &str,
+    score_refiners: &[Arc<dyn ScoreRefiner + 'static>],
+    blend: RefinerBlend,
+    budget: Option<Duration>,
+) -> Box<[SearchResultWithMetadata]> {
+    let deadline = budget.map(|budget| Instant::now() + budget);
+
+    for (stage, score_refiner) in score_refiners.iter().enumerate() {
+        let remaining_budget =
+            deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+
+        if remaining_budget.is_some_and(|remaining_budget| remaining_budget.is_zero()) {
+            warn!("Skipped score refiner stages from {stage} onwards as the time budget ran out.");
+            break;
+        }
+
+        refine_stage(
+            &mut results,
+            stage,
+            query,
+            score_refiner.as_ref(),
+            blend,
+            remaining_budget,
+        )
+        .await;
+    }
+
+    results
+}
+
+/// Refines the scores of the top results, leaving them as they are if the refiner
+/// fails or doesn't finish within the time budget.
+async fn refine_stage(
+    results: &mut [SearchResultWithMetadata],
+    stage: usize,
+    query: &str,
+    score_refiner: &dyn ScoreRefiner,
+    blend: RefinerBlend,
+    budget: Option<Duration>,
+) {
+    let top_results_count = score_refiner.max_results().min(results.len());
+    if top_results_count == 0 {
+        return;
+    }
+
+    let top_results = &mut results[0..top_results_count];
+
+    let top_summaries = top_results
+        .iter()
+        .map(|result| result.search_result.snippet())
+        .collect::<Box<[String]>>();
+
+    let span = info_span!("refine", stage, results = top_results_count);
+    let scores = score_refiner.scores(query, &top_summaries).instrument(span);
+
+    let top_scores = match budget {
+        Some(budget) => match tokio::time::timeout(budget, scores).await {
+            Ok(top_scores) => top_scores,
+            Err(_) => {
+                warn!("Skipped score refinement as it took longer than {budget:?}.");
+                return;
+            }
+        },
+        None => scores.await,
+    };
+
+    let top_scores = match top_scores {
+        Ok(top_scores) => top_scores,
+        Err(err) => {
+            warn!("Skipped score refinement as the refiner failed: {err}");
+            return;
+        }
+    };
+
+    let previous_scores: Box<[f32]> = top_results
+        .iter()
+        .map(|result| result.relevance_score)
+        .collect();
+
+    let refined_scores: Box<[Option<f32>]> = (0..top_results_count)
+        .map(|idx| match top_scores.get(idx) {
+            Some(Ok(score)) if score.is_finite() => Some(*score),
+            _ => None,
+        })
+        .collect();
+
+    let previous_scale = Scale::new(blend.normalization, previous_scores.iter().copied());
+    let refined_scale = Scale::new(
+        blend.normalization,
+        refined_scores.iter().flatten().copied(),
+    );
+
+    let refined_weight = blend.refined_weight.clamp(0., 1.);
+
+    for (idx, result) in top_results.iter_mut().enumerate() {
+        let previous_score = previous_scale.normalize(previous_scores[idx]);
+
+        // Results the refiner couldn't score keep their previous score.
+        let blended_score = match refined_scores[idx] {
+            Some(refined_score) => {
+                refined_weight * refined_scale.normalize(refined_score)
+                    + (1. - refined_weight) * previous_score
+            }
+            None => previous_score,
+        };
+
+        result.relevance_score = blended_score;
+
+        if let Some(refined_score) = refined_scores[idx] {
+            result.refined_scores.push((stage, refined_score));
+        }
+    }
+
+    top_results.par_sort_unstable_by(|a, b| b.relevance_score.total_cmp(&a.relevance_score));
+
+    // Mapping back onto the previous scale keeps the refined results
+    // comparable with (and ahead of) the results which weren't refined.
+    for result in top_results.iter_mut() {
+        result.relevance_score = previous_scale.denormalize(result.relevance_score);
+    }
+}
+
+/// The statistics needed to normalise a set of scores and map them back again.
+struct Scale {
+    normalization: ScoreNormalization,
+    min: f32,
+    max: f32,
+    mean: f32,
+    std_dev: f32,
+}
+
+impl Scale {
+    fn new(normalization: ScoreNormalization, scores: impl Iterator<Item = f32> + Clone) -> Self {
+        let count = scores.clone().count().max(1) as f32;
+
+        let (min, max) = scores
+            .clone()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), score| {
+                (min.min(score), max.max(score))
+            });
+
+        let mean = scores.clone().sum::<f32>() / count;
+        let variance = scores.map(|score| (score - mean).powi(2)).sum::<f32>() / count;
+
+        Self {
+            normalization,
+            min,
+            max,
+            mean,
+            std_dev: variance.sqrt(),
+        }
+    }
+
+    fn normalize(&self, score: f32) -> f32 {
+        match self.normalization {
+            ScoreNormalization::MinMax if self.max > self.min => {
+                (score - self.min) / (self.max - self.min)
+            }
+            ScoreNormalization::ZScore if self.std_dev > 0. => (score - self.mean) / self.std_dev,
+            // Every score is the same, so they're all equally relevant.
+            ScoreNormalization::MinMax => 1.,
+            ScoreNormalization::ZScore => 0.,
+        }
+    }
+
+    fn denormalize(&self, score: f32) -> f32 {
+        let score = match self.normalization {
+            ScoreNormalization::MinMax => self.min + score * (self.max - self.min),
+            ScoreNormalization::ZScore => self.mean + score * self.std_dev,
+        };
+
+        score.clamp(self.min, self.max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quaero_shared::models::search::{SearchResult, SearchResultKind};
+
+    use super::*;
+
+    /// A refiner which returns fixed scores, optionally after a delay.
+    struct FixedScores {
+        scores: Option<Vec<Option<f32>>>,
+        delay: Duration,
+    }
+
+    impl FixedScores {
+        fn new(scores: impl IntoIterator<Item = f32>) -> Self {
+            Self {
+                scores: Some(scores.into_iter().map(Some).collect()),
+                delay: Duration::ZERO,
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ScoreRefiner for FixedScores {
+        async fn scores(
+            &self,
+            _query: &str,
+            targets: &[String],
+        ) -> anyhow::Result<Box<[anyhow::Result<f32>]>> {
+            tokio::time::sleep(self.delay).await;
+
+            let scores = self
+                .scores
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("refiner failed"))?;
+
+            Ok(scores
+                .iter()
+                .take(targets.len())
+                .map(|score| score.ok_or_else(|| anyhow::anyhow!("no score")))
+                .collect())
+        }
+
+        fn max_results(&self) -> usize {
+            10
+        }
+    }
+
+    /// Creates results titled `a`, `b`, `c`... with the specified relevance scores.
+    fn results(scores: &[f32]) -> Box<[SearchResultWithMetadata]> {
+        let engine_id = "Google:V1StGXR8_Z".parse().unwrap();
+
+        scores
+            .iter()
+            .enumerate()
+            .map(|(idx, score)| {
+                let title = char::from(b'a' + idx as u8).to_string();
+                let url_key = format!("example.com/{title}");
+                let search_result = SearchResult {
+                    url: format!("https://{url_key}"),
+                    title,
+                    summary: String::new(),
+                    kind: SearchResultKind::Web,
+                };
+
+                let mut result =
+                    SearchResultWithMetadata::new(url_key, search_result, &engine_id, idx);
+                result.relevance_score = *score;
+                result
+            })
+            .collect()
+    }
+
+    async fn refine(
+        scores: &[f32],
+        refiners: Vec<FixedScores>,
+        normalization: ScoreNormalization,
+        budget: Option<Duration>,
+    ) -> Vec<(String, f32)> {
+        let refiners: Vec<Arc<dyn ScoreRefiner>> = refiners
+            .into_iter()
+            .map(|refiner| Arc::new(refiner) as Arc<dyn ScoreRefiner>)
+            .collect();
+
+        let blend = RefinerBlend {
+            normalization,
+            refined_weight: 0.7,
+        };
+
+        refine_results(results(scores), "query", &refiners, blend, budget)
+            .await
+            .iter()
+            .map(|result| (result.search_result.title.clone(), result.relevance_score))
+            .collect()
+    }
+
+    fn assert_results(results: &[(String, f32)], expected: &[(&str, f32)]) {
+        assert_eq!(results.len(), expected.len());
+
+        for ((title, score), (expected_title, expected_score)) in results.iter().zip(expected) {
+            assert_eq!(title, expected_title);
+            assert!(
+                (score - expected_score).abs() < 1e-4,
+                "{title} has a score of {score}, expected {expected_score}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn min_max_blending() {
+        let results = refine(
+            &[3., 2., 1.],
+            vec![FixedScores::new([0., 0.5, 1.])],
+            ScoreNormalization::MinMax,
+            None,
+        )
+        .await;
+
+        // The blended scores (0.3, 0.5 and 0.7) are mapped back onto the previous scale.
+        assert_results(&results, &[("c", 2.4), ("b", 2.), ("a", 1.6)]);
+    }
+
+    #[tokio::test]
+    async fn z_score_blending() {
+        let results = refine(
+            &[3., 2., 1.],
+            vec![FixedScores::new([10., 20., 90.])],
+            ScoreNormalization::ZScore,
+            None,
+        )
+        .await;
+
+        // The outlier dominates the refined scores, while `a` and `b` stay close together.
+        assert_results(&results, &[("c", 2.50296), ("a", 1.81823), ("b", 1.67882)]);
+    }
+
+    #[tokio::test]
+    async fn constant_scores() {
+        for normalization in [ScoreNormalization::MinMax, ScoreNormalization::ZScore] {
+            // Constant refined scores keep the previous order.
+            let results = refine(
+                &[3., 2., 1.],
+                vec![FixedScores::new([5., 5., 5.])],
+                normalization,
+                None,
+            )
+            .await;
+
+            let titles: Vec<&str> = results.iter().map(|(title, _)| title.as_str()).collect();
+            assert_eq!(titles, ["a", "b", "c"]);
+            assert!(results.iter().all(|(_, score)| score.is_finite()));
+
+            // Constant previous scores are reordered by the refined scores, but keep their value.
+            let results = refine(
+                &[2., 2., 2.],
+                vec![FixedScores::new([1., 2., 3.])],
+                normalization,
+                None,
+            )
+            .await;
+
+            assert_results(&results, &[("c", 2.), ("b", 2.), ("a", 2.)]);
+        }
+    }
+
+    #[tokio::test]
+    async fn refiner_errors() {
+        let unchanged = [("a", 3.), ("b", 2.), ("c", 1.)];
+
+        let failing = FixedScores {
+            scores: None,
+            delay: Duration::ZERO,
+        };
+        let results = refine(
+            &[3., 2., 1.],
+            vec![failing],
+            ScoreNormalization::MinMax,
+            None,
+        )
+        .await;
+        assert_results(&results, &unchanged);
+
+        // Results the refiner couldn't score are blended with only their previous score.
+        let partial = FixedScores {
+            scores: Some(vec![None, Some(0.), Some(1.)]),
+            delay: Duration::ZERO,
+        };
+        let results = refine(
+            &[3., 2., 1.],
+            vec![partial],
+            ScoreNormalization::MinMax,
+            None,
+        )
+        .await;
+        assert_results(&results, &[("a", 3.), ("c", 2.4), ("b", 1.3)]);
+    }
+
+    #[tokio::test]
+    async fn budget_timeout() {
+        let slow = || FixedScores {
+            delay: Duration::from_millis(200),
+            ..FixedScores::new([0., 0.5, 1.])
+        };
+
+        let results = refine(
+            &[3., 2., 1.],
+            vec![slow()],
+            ScoreNormalization::MinMax,
+            Some(Duration::from_millis(20)),
+        )
+        .await;
+        assert_results(&results, &[("a", 3.), ("b", 2.), ("c", 1.)]);
+
+        // Later stages are skipped once the budget has run out, keeping the last stage's order.
+        let results = refine(
+            &[3., 2., 1.],
+            vec![FixedScores::new([0., 0.5, 1.]), slow(), slow()],
+            ScoreNormalization::MinMax,
+            Some(Duration::from_millis(20)),
+        )
+        .await;
+        assert_results(&results, &[("c", 2.4), ("b", 2.), ("a", 1.6)]);
+    }
+}
@@ -0,0 +1,101 @@
+//! Policies for retrying and hedging requests to an engine.
+
+use std::time::Duration;
+
+use quaero_shared::models::search::SearchError;
+
+/// Controls how many times a failed request to an engine is retried, and
+/// whether a hedged request is fired when the engine is slower than usual.
+///
+/// All attempts share the quaero instance's timeout, so retries are only
+/// made if there is enough time left to make them.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// The maximum amount of attempts, including the first one.
+    pub max_attempts: u32,
+
+    /// How long to wait before the first retry.
+    pub initial_backoff: Duration,
+
+    /// The longest amount of time to wait between retries.
+    /// The backoff doubles after each retry, up until this limit.
+    pub max_backoff: Duration,
+
+    /// The errors which are worth retrying.
+    pub retryable: Vec<SearchError>,
+
+    /// Fires a second request if the first one is slower than usual.
+    pub hedging: Option<Hedging>,
+}
+
+impl RetryPolicy {
+    /// A policy which never retries or hedges requests.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+            retryable: Vec::new(),
+            hedging: None,
+        }
+    }
+
+    /// A policy which retries transient failures up to `max_attempts` times in total.
+    pub fn retries(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            ..Default::default()
+        }
+    }
+
+    /// Fires a second request if the first one hasn't answered by the specified
+    /// percentile of the engine's recent latencies.
+    pub fn hedging(mut self, hedging: Hedging) -> Self {
+        self.hedging = Some(hedging);
+        self
+    }
+
+    /// Returns whether the error is worth retrying.
+    pub fn is_retryable(&self, search_error: &SearchError) -> bool {
+        self.retryable.contains(search_error)
+    }
+
+    /// Returns how long to wait before the specified retry (starting at 1).
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(2),
+            retryable: vec![SearchError::RequestFailed, SearchError::NoResponseText],
+            hedging: None,
+        }
+    }
+}
+
+/// Controls when a hedged request is fired.
+#[derive(Debug, Clone, Copy)]
+pub struct Hedging {
+    /// The percentile (between `0.0` and `1.0`) of the engine's recent latencies
+    /// after which a hedged request is fired.
+    pub percentile: f32,
+
+    /// How many latencies need to have been recorded for the engine before requests are hedged.
+    pub min_samples: usize,
+}
+
+impl Default for Hedging {
+    fn default() -> Self {
+        Self {
+            percentile: 0.95,
+            min_samples: 10,
+        }
+    }
+}
@@ -0,0 +1,398 @@
+//! Paginates through a search without repeating results across pages.
+
+use anyhttp::HttpClient;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+};
+
+use quaero_shared::models::{
+    engine::{EngineId, TaggedEngine},
+    search::{CacheStatus, SearchError, SearchOptions, SearchResponse, SearchResultWithMetadata},
+};
+
+use crate::{
+    Quaero,
+    aggregator::{SearchPlan, aggregate_results},
+};
+
+const DEFAULT_PAGE_SIZE: usize = 10;
+const DEFAULT_MAX_FETCHES: usize = 3;
+
+/// Remembers which search results have already been shown and how far each engine
+/// has been paged through, so that each page only contains new search results.
+///
+/// Engines often return the same result on different pages, so a session keeps
+/// fetching further pages until it has enough new results to fill a page.
+#[derive(Clone)]
+pub struct SearchSession {
+    query: String,
+    options: Arc<SearchOptions>,
+    page_size: usize,
+    max_fetches: usize,
+    // The strict url keys of every result which has been shown or is pending.
+    seen: HashSet<String>,
+    // The urls of every result which has been shown or is pending, including those of
+    // near-duplicates which were collapsed into it.
+    seen_urls: HashSet<String>,
+    // The next page number each engine should be fetched from.
+    page_cursors: HashMap<EngineId, usize>,
+    // Engines which have run out of results.
+    exhausted_engines: HashSet<EngineId>,
+    // Engines which were skipped the last time they were queried, as they were
+    // temporarily disabled or over their rate limit.
+    skipped_engines: HashSet<EngineId>,
+    // New results which didn't fit into the previous page.
+    pending: VecDeque<SearchResultWithMetadata>,
+}
+
+impl SearchSession {
+    /// Creates a new session which starts at the options' page number.
+    pub fn new(query: impl AsRef<str>, options: impl Into<Arc<SearchOptions>>) -> Self {
+        Self {
+            query: query.as_ref().to_string(),
+            options: options.into(),
+            page_size: DEFAULT_PAGE_SIZE,
+            max_fetches: DEFAULT_MAX_FETCHES,
+            seen: HashSet::new(),
+            seen_urls: HashSet::new(),
+            page_cursors: HashMap::new(),
+            exhausted_engines: HashSet::new(),
+            skipped_engines: HashSet::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Sets how many search results each page should contain.
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Sets how many times the engines can be queried when filling a single page.
+    pub fn max_fetches(mut self, max_fetches: usize) -> Self {
+        self.max_fetches = max_fetches.max(1);
+        self
+    }
+
+    /// Returns the strict url keys of every search result the session has seen so far.
+    pub fn seen(&self) -> &HashSet<String> {
+        &self.seen
+    }
+
+    /// Returns whether every engine has run out of search results.
+    ///
+    /// Engines which don't support the search's category are never queried, and engines
+    /// which were skipped as they were temporarily disabled or over their rate limit
+    /// can't be relied on to return more results, so both count as having run out.
+    pub fn is_exhausted<C: HttpClient + 'static, const N: usize>(
+        &self,
+        quaero: &Quaero<C, N>,
+    ) -> bool {
+        self.pending.is_empty()
+            && quaero
+                .engines
+                .load()
+                .enabled()
+                .filter(|TaggedEngine(engine_id, engine, _)| {
+                    self.options.includes_engine(engine_id)
+                        && engine.categories().contains(&self.options.category)
+                })
+                .all(|TaggedEngine(engine_id, ..)| {
+                    self.exhausted_engines.contains(engine_id)
+                        || self.skipped_engines.contains(engine_id)
+                })
+    }
+
+    /// Fetches the next page of search results, skipping any which have already been shown.
+    ///
+    /// The statuses of the response are those of the last time each engine was queried.
+    /// Responses are never cached as they depend on the session's state.
+    pub async fn next_page<C: HttpClient + Send + Sync + 'static, const N: usize>(
+        &mut self,
+        quaero: &Quaero<C, N>,
+    ) -> SearchResponse {
+        let mut statuses: Vec<(EngineId, Result<(), SearchError>)> = Vec::new();
+
+        for _ in 0..self.max_fetches {
+            if self.pending.len() >= self.page_size || self.is_exhausted(quaero) {
+                break;
+            }
+
+            let plan = SearchPlan {
+                page_cursors: self.page_cursors.clone(),
+                excluded_engines: self.exhausted_engines.clone(),
+            };
+
+            let response =
+                aggregate_results(quaero, &self.query, self.options.clone(), &plan).await;
+
+            if !self.absorb(quaero, response, &mut statuses) {
+                break;
+            }
+        }
+
+        let page_len = self.page_size.min(self.pending.len());
+
+        SearchResponse {
+            results: self.pending.drain(..page_len).collect(),
+            statuses,
+            cache_status: CacheStatus::Miss,
+        }
+    }
+
+    /// Updates the session with a response, queueing any results which haven't been seen
+    /// and merging its statuses into `statuses`. Returns whether any engine succeeded.
+    fn absorb<C: HttpClient + 'static, const N: usize>(
+        &mut self,
+        quaero: &Quaero<C, N>,
+        response: SearchResponse,
+        statuses: &mut Vec<(EngineId, Result<(), SearchError>)>,
+    ) -> bool {
+        let mut any_succeeded = false;
+
+        for (engine_id, status) in &response.statuses {
+            match status {
+                Ok(()) => {
+                    any_succeeded = true;
+                    self.skipped_engines.remove(engine_id);
+                    self.advance_cursor(quaero, engine_id);
+                }
+                Err(SearchError::NoResultsFound) => {
+                    self.exhausted_engines.insert(engine_id.clone());
+                }
+                Err(SearchError::TemporarilyDisabled | SearchError::RateLimited) => {
+                    self.skipped_engines.insert(engine_id.clone());
+                }
+                // Other errors may be transient, so the same page is retried next time.
+                Err(_) => {}
+            }
+
+            match statuses
+                .iter_mut()
+                .find(|(existing_id, _)| existing_id == engine_id)
+            {
+                Some((_, existing_status)) => *existing_status = status.clone(),
+                None => statuses.push((engine_id.clone(), status.clone())),
+            }
+        }
+
+        for result in response.results {
+            // A result may have been collapsed into a near-duplicate with a different url
+            // on an earlier page, or the other way around.
+            let urls = || std::iter::once(&result.search_result.url).chain(&result.also_at);
+
+            if self.seen.contains(&result.url_key) || urls().any(|url| self.seen_urls.contains(url))
+            {
+                continue;
+            }
+
+            self.seen.insert(result.url_key.clone());
+            self.seen_urls.extend(urls().cloned());
+            self.pending.push_back(result);
+        }
+
+        any_succeeded
+    }
+
+    /// Moves the engine's cursor past the pages it was just queried for.
+    fn advance_cursor<C: HttpClient + 'static, const N: usize>(
+        &mut self,
+        quaero: &Quaero<C, N>,
+        engine_id: &EngineId,
+    ) {
+        let engines = quaero.engines.load();
+
+        let Some(TaggedEngine(_, engine, _)) = engines.get(engine_id) else {
+            return;
+        };
+
+        let pages_fetched = self
+            .options
+            .page_range(engine.category_results_per_page(self.options.category))
+            .len();
+
+        *self
+            .page_cursors
+            .entry(engine_id.clone())
+            .or_insert(self.options.page_num) += pages_fetched;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quaero_shared::models::{
+        engine::Engine,
+        search::{SearchCategory, SearchResult, SearchResultKind},
+    };
+
+    use super::*;
+
+    struct OfflineClient;
+
+    #[async_trait::async_trait]
+    impl HttpClient for OfflineClient {
+        async fn execute(
+            &self,
+            _request: http::Request<Vec<u8>>,
+        ) -> anyhow::Result<anyhttp::Response> {
+            anyhow::bail!("offline")
+        }
+    }
+
+    struct TestEngine(&'static [SearchCategory]);
+
+    impl Engine for TestEngine {
+        fn homepage(&self) -> &'static str {
+            "https://example.com"
+        }
+
+        fn categories(&self) -> &'static [SearchCategory] {
+            self.0
+        }
+
+        fn url(&self, query: &str, _options: &SearchOptions) -> Result<String, SearchError> {
+            Ok(format!("https://example.com/search?q={query}"))
+        }
+
+        fn parse<'a>(
+            &self,
+            _response_text: String,
+        ) -> Result<Vec<(String, SearchResult)>, SearchError> {
+            Err(SearchError::NoResultsFound)
+        }
+    }
+
+    fn quaero(engines: &[TaggedEngine]) -> Quaero<OfflineClient> {
+        let quaero = Quaero::empty(OfflineClient);
+        quaero.extend_engines(engines.iter().cloned());
+        quaero
+    }
+
+    fn result(engine: &TaggedEngine, url: &str, also_at: &[&str]) -> SearchResultWithMetadata {
+        let search_result = SearchResult {
+            title: url.to_string(),
+            url: format!("https://{url}"),
+            summary: String::new(),
+            kind: SearchResultKind::Web,
+        };
+
+        let mut result =
+            SearchResultWithMetadata::new(url.to_string(), search_result, &engine.0, 0);
+        result.also_at = also_at.iter().map(|url| format!("https://{url}")).collect();
+        result
+    }
+
+    fn response(
+        statuses: Vec<(&TaggedEngine, Result<(), SearchError>)>,
+        results: Vec<SearchResultWithMetadata>,
+    ) -> SearchResponse {
+        SearchResponse {
+            results: results.into_boxed_slice(),
+            statuses: statuses
+                .into_iter()
+                .map(|(engine, status)| (engine.0.clone(), status))
+                .collect(),
+            cache_status: CacheStatus::Miss,
+        }
+    }
+
+    fn pending_urls(session: &mut SearchSession) -> Vec<String> {
+        session
+            .pending
+            .drain(..)
+            .map(|result| result.url_key)
+            .collect()
+    }
+
+    #[test]
+    fn skips_results_seen_on_earlier_pages() {
+        let engine = TaggedEngine::new(TestEngine(&[SearchCategory::Web]));
+        let quaero = quaero(std::slice::from_ref(&engine));
+        let mut session = SearchSession::new("rust", SearchOptions::default());
+        let mut statuses = Vec::new();
+
+        let first_page = response(
+            vec![(&engine, Ok(()))],
+            vec![
+                result(&engine, "example.com/a", &["m.example.com/a"]),
+                result(&engine, "example.com/b", &[]),
+            ],
+        );
+        assert!(session.absorb(&quaero, first_page, &mut statuses));
+        assert_eq!(
+            pending_urls(&mut session),
+            ["example.com/a", "example.com/b"]
+        );
+
+        // The engine is paged through rather than queried for the same page again.
+        assert_eq!(session.page_cursors[&engine.0], 1);
+
+        let second_page = response(
+            vec![(&engine, Ok(()))],
+            vec![
+                // Seen under the same url key.
+                result(&engine, "example.com/b", &[]),
+                // Collapsed into a result on the first page.
+                result(&engine, "m.example.com/a", &[]),
+                // Has a result from the first page collapsed into it.
+                result(&engine, "amp.example.com/b", &["example.com/b"]),
+                result(&engine, "example.com/c", &[]),
+            ],
+        );
+        assert!(session.absorb(&quaero, second_page, &mut statuses));
+        assert_eq!(pending_urls(&mut session), ["example.com/c"]);
+        assert_eq!(session.page_cursors[&engine.0], 2);
+    }
+
+    #[test]
+    fn exhausted_once_every_engine_has_run_out() {
+        let exhausted = TaggedEngine::new(TestEngine(&[SearchCategory::Web]));
+        let disabled = TaggedEngine::new(TestEngine(&[SearchCategory::Web]));
+        let rate_limited = TaggedEngine::new(TestEngine(&[SearchCategory::Web]));
+        let images_only = TaggedEngine::new(TestEngine(&[SearchCategory::Images]));
+
+        let quaero = quaero(&[
+            exhausted.clone(),
+            disabled.clone(),
+            rate_limited.clone(),
+            images_only,
+        ]);
+        let mut session = SearchSession::new("rust", SearchOptions::default());
+        let mut statuses = Vec::new();
+
+        assert!(!session.is_exhausted(&quaero));
+
+        let page = response(
+            vec![
+                (&exhausted, Ok(())),
+                (&disabled, Err(SearchError::TemporarilyDisabled)),
+                (&rate_limited, Err(SearchError::Timeout)),
+            ],
+            vec![result(&exhausted, "example.com/a", &[])],
+        );
+        session.absorb(&quaero, page, &mut statuses);
+        assert!(!session.is_exhausted(&quaero));
+
+        let page = response(
+            vec![
+                (&exhausted, Err(SearchError::NoResultsFound)),
+                (&rate_limited, Err(SearchError::RateLimited)),
+            ],
+            Vec::new(),
+        );
+        assert!(!session.absorb(&quaero, page, &mut statuses));
+
+        // Results which haven't been shown yet still need to be paged through.
+        assert!(!session.is_exhausted(&quaero));
+        session.pending.clear();
+        assert!(session.is_exhausted(&quaero));
+
+        // An engine which recovers is no longer treated as having run out.
+        let page = response(vec![(&disabled, Ok(()))], Vec::new());
+        assert!(session.absorb(&quaero, page, &mut statuses));
+        assert!(!session.is_exhausted(&quaero));
+
+        assert_eq!(statuses.len(), 3);
+    }
+}